@@ -0,0 +1,69 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic_config::CosmicConfigEntry;
+use serde::{Deserialize, Serialize};
+
+pub const ID: &str = "com.system76.CosmicSettings.Brightness";
+
+/// Gets a cosmic-config [Config] context.
+pub fn context() -> Result<cosmic_config::Config, cosmic_config::Error> {
+    Config::context()
+}
+
+/// Lowest display brightness that may be set without explicitly turning the backlight off.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub enum MinimumBrightness {
+    /// A raw backlight level.
+    Absolute(u32),
+    /// A percentage of the perceptual brightness scale.
+    Percent(f64),
+}
+
+impl Default for MinimumBrightness {
+    fn default() -> Self {
+        Self::Absolute(1)
+    }
+}
+
+/// cosmic-config configuration state for `com.system76.CosmicSettings.Brightness`
+#[derive(Clone, Debug, PartialEq, CosmicConfigEntry)]
+#[version = 1]
+pub struct Config {
+    pub minimum_brightness: MinimumBrightness,
+    /// Duration of display brightness transitions in milliseconds, or 0 to apply them instantly.
+    pub fade_duration_ms: u64,
+    /// Adjust the display brightness to the ambient light level.
+    pub auto_brightness: bool,
+    /// Points of `(illuminance in lux, brightness in percent)` to interpolate between.
+    pub auto_brightness_curve: Vec<(f64, f64)>,
+    /// Change in brightness percent required before automatic brightness applies it.
+    pub auto_brightness_hysteresis: f64,
+    /// Remember separate display brightness levels on AC and battery power.
+    pub power_source_profiles: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            minimum_brightness: MinimumBrightness::default(),
+            fade_duration_ms: 0,
+            auto_brightness: false,
+            auto_brightness_curve: vec![
+                (0.0, 5.0),
+                (10.0, 20.0),
+                (100.0, 40.0),
+                (1000.0, 70.0),
+                (10000.0, 100.0),
+            ],
+            auto_brightness_hysteresis: 5.0,
+            power_source_profiles: false,
+        }
+    }
+}
+
+impl Config {
+    pub fn context() -> Result<cosmic_config::Config, cosmic_config::Error> {
+        cosmic_config::Config::new(ID, Self::VERSION)
+    }
+}
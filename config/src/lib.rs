@@ -1,5 +1,8 @@
 // SPDX-License-Identifier: MPL-2.0
 
+pub mod brightness;
+pub mod power;
 pub mod shortcuts;
 pub use shortcuts::{Action, Binding, Shortcuts};
+pub mod sound;
 pub mod window_rules;
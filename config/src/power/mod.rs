@@ -0,0 +1,102 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic_config::CosmicConfigEntry;
+use serde::{Deserialize, Serialize};
+
+pub const ID: &str = "com.system76.CosmicSettings.Power";
+
+/// Gets a cosmic-config [Config] context.
+pub fn context() -> Result<cosmic_config::Config, cosmic_config::Error> {
+    Config::context()
+}
+
+/// What to do when the battery reaches the action threshold.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum CriticalAction {
+    /// Only notify the user.
+    #[default]
+    Notify,
+    Suspend,
+    Hibernate,
+    PowerOff,
+}
+
+/// How the battery warning thresholds are measured.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum WarningMode {
+    /// Battery percentage.
+    #[default]
+    Percentage,
+    /// Estimated time until the battery is empty, falling back to the
+    /// percentage while there is no estimate.
+    TimeRemaining,
+}
+
+/// cosmic-config configuration state for `com.system76.CosmicSettings.Power`
+#[derive(Clone, Debug, PartialEq, CosmicConfigEntry)]
+#[version = 1]
+pub struct Config {
+    pub warning_mode: WarningMode,
+    /// Battery percentage below which the battery is considered low.
+    pub low_percentage: f64,
+    /// Battery percentage below which the battery is considered critical.
+    pub critical_percentage: f64,
+    /// Battery percentage below which the critical action is taken.
+    pub action_percentage: f64,
+    /// Seconds remaining below which the battery is considered low, in time remaining mode.
+    pub low_time_secs: u64,
+    /// Seconds remaining below which the battery is considered critical, in time remaining mode.
+    pub critical_time_secs: u64,
+    /// Seconds remaining below which the critical action is taken, in time remaining mode.
+    pub action_time_secs: u64,
+    pub critical_action: CriticalAction,
+    /// Seconds the user has to cancel the critical action before it is taken.
+    pub critical_action_delay_secs: u64,
+    /// Battery percentage below which a wireless peripheral's battery is considered low.
+    pub peripheral_low_percentage: f64,
+    /// Battery percentage below which a wireless peripheral's battery is considered critical.
+    pub peripheral_critical_percentage: f64,
+    /// Limit battery charging to the charge thresholds to preserve battery health.
+    pub preserve_battery_health: bool,
+    /// Battery percentage below which charging starts, when preserving battery health.
+    pub charge_start_threshold: u8,
+    /// Battery percentage at which charging stops, when preserving battery health.
+    pub charge_end_threshold: u8,
+    /// Switch to the power saver profile when the battery runs low.
+    pub low_battery_power_saver: bool,
+    /// Power profile to switch to when AC is plugged in.
+    pub ac_power_profile: Option<String>,
+    /// Power profile to switch to when running on battery.
+    pub battery_power_profile: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            warning_mode: WarningMode::default(),
+            low_percentage: 20.0,
+            critical_percentage: 10.0,
+            action_percentage: 5.0,
+            low_time_secs: 15 * 60,
+            critical_time_secs: 5 * 60,
+            action_time_secs: 2 * 60,
+            critical_action: CriticalAction::default(),
+            critical_action_delay_secs: 60,
+            peripheral_low_percentage: 15.0,
+            peripheral_critical_percentage: 5.0,
+            preserve_battery_health: false,
+            charge_start_threshold: 75,
+            charge_end_threshold: 80,
+            low_battery_power_saver: true,
+            ac_power_profile: None,
+            battery_power_profile: None,
+        }
+    }
+}
+
+impl Config {
+    pub fn context() -> Result<cosmic_config::Config, cosmic_config::Error> {
+        cosmic_config::Config::new(ID, Self::VERSION)
+    }
+}
@@ -0,0 +1,73 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use cosmic_config::cosmic_config_derive::CosmicConfigEntry;
+use cosmic_config::CosmicConfigEntry;
+
+pub const ID: &str = "com.system76.CosmicSettings.Sound";
+
+/// Gets a cosmic-config [Config] context.
+pub fn context() -> Result<cosmic_config::Config, cosmic_config::Error> {
+    Config::context()
+}
+
+/// cosmic-config configuration state for `com.system76.CosmicSettings.Sound`
+#[derive(Clone, Debug, PartialEq, CosmicConfigEntry)]
+#[version = 1]
+pub struct Config {
+    /// XDG sound theme to play event sounds from.
+    pub theme: String,
+    /// Play event sounds at all.
+    pub event_sounds: bool,
+    /// Play a sound when AC power is plugged in.
+    pub power_plug_sound: bool,
+    /// Play a sound when AC power is unplugged.
+    pub power_unplug_sound: bool,
+    /// Play a sound when the battery runs low or critical.
+    pub battery_low_sound: bool,
+    /// Play a sound when the battery is fully charged.
+    pub battery_full_sound: bool,
+    /// Volume of event sounds, from 0.0 to 1.0.
+    pub volume: f32,
+    /// Percent by which volume keys change the volume of the default devices.
+    pub volume_step: u32,
+    /// Maximum volume in percent that volume keys raise to, above 100 to
+    /// allow boosting quiet output.
+    pub max_volume: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            theme: "Pop".to_owned(),
+            event_sounds: true,
+            power_plug_sound: true,
+            power_unplug_sound: true,
+            battery_low_sound: true,
+            battery_full_sound: true,
+            volume: 1.0,
+            volume_step: 5,
+            max_volume: 100,
+        }
+    }
+}
+
+impl Config {
+    pub fn context() -> Result<cosmic_config::Config, cosmic_config::Error> {
+        cosmic_config::Config::new(ID, Self::VERSION)
+    }
+
+    /// Whether the sound for an XDG sound naming spec event ID should play.
+    pub fn event_enabled(&self, event_id: &str) -> bool {
+        if !self.event_sounds {
+            return false;
+        }
+
+        match event_id {
+            "power-plug" => self.power_plug_sound,
+            "power-unplug" | "power-unplug-battery-low" => self.power_unplug_sound,
+            "battery-caution" | "battery-low" => self.battery_low_sound,
+            "battery-full" => self.battery_full_sound,
+            _ => true,
+        }
+    }
+}
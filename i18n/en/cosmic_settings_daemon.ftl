@@ -0,0 +1,33 @@
+power = Power
+
+## Battery notifications
+
+battery-low = Battery Low
+battery-critical = Battery Critical
+battery-remaining = { $percent }% remaining
+battery-time-remaining =
+    { $hours ->
+        [0] About { $minutes } min remaining ({ $percent }%)
+       *[other] About { $hours } h { $minutes } min remaining ({ $percent }%)
+    }
+enable-power-saver = Enable power saver
+open-power-settings = Open power settings
+
+critical-action-countdown =
+    The computer will { $action ->
+        [suspend] suspend
+        [hibernate] hibernate
+       *[power-off] power off
+    } in { $seconds } seconds unless it is plugged in.
+cancel = Cancel
+
+## Peripheral battery notifications
+
+peripheral-battery-low = { $device }: Battery Low
+peripheral-battery-critical = { $device }: Battery Critical
+mouse = Mouse
+keyboard = Keyboard
+game-controller = Game controller
+pen = Pen
+headset = Headset
+headphones = Headphones
@@ -0,0 +1,394 @@
+// Copyright 2025 System76 <info@system76.com>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::future::Future;
+
+use cosmic_settings_config::brightness;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio_stream::StreamExt;
+
+use crate::{sensor_proxy::SensorProxy, SettingsDaemon, DBUS_PATH};
+
+/// Illuminance must change by this factor from where the user last adjusted
+/// the brightness before automatic brightness resumes.
+const RESUME_FACTOR: f64 = 2.0;
+
+#[derive(Debug)]
+pub enum Event {
+    /// The user manually changed the display brightness.
+    ManualChange,
+    /// The brightness configuration changed.
+    Config(brightness::Config),
+}
+
+/// Computes display brightness targets from ambient light readings.
+pub struct AutoBrightness {
+    curve: Vec<(f64, f64)>,
+    hysteresis: f64,
+    last_target: Option<f64>,
+    last_lux: Option<f64>,
+    paused_at: Option<f64>,
+}
+
+impl AutoBrightness {
+    pub fn new(config: &brightness::Config) -> Self {
+        let mut auto_brightness = Self {
+            curve: Vec::new(),
+            hysteresis: 0.0,
+            last_target: None,
+            last_lux: None,
+            paused_at: None,
+        };
+        auto_brightness.set_config(config);
+        auto_brightness
+    }
+
+    pub fn set_config(&mut self, config: &brightness::Config) {
+        self.curve = config.auto_brightness_curve.clone();
+        self.curve.sort_by(|a, b| a.0.total_cmp(&b.0));
+        self.hysteresis = config.auto_brightness_hysteresis.max(0.0);
+        self.last_target = None;
+    }
+
+    /// Stop adjusting the brightness until the ambient light changes significantly.
+    pub fn pause(&mut self) {
+        self.paused_at = Some(self.last_lux.unwrap_or(0.0));
+        self.last_target = None;
+    }
+
+    /// Feed an illuminance reading, in lux, returning the brightness percent
+    /// to apply if it should change.
+    pub fn update(&mut self, lux: f64) -> Option<f64> {
+        self.last_lux = Some(lux);
+
+        if let Some(paused_at) = self.paused_at {
+            if lux > paused_at / RESUME_FACTOR && lux < paused_at.max(1.0) * RESUME_FACTOR {
+                return None;
+            }
+            self.paused_at = None;
+        }
+
+        let target = curve_percent(&self.curve, lux);
+        if self
+            .last_target
+            .is_some_and(|last| (last - target).abs() < self.hysteresis)
+        {
+            return None;
+        }
+
+        self.last_target = Some(target);
+        Some(target)
+    }
+}
+
+/// Interpolate a brightness percent from a sorted curve of `(lux, percent)`
+/// points. Perception of light is roughly logarithmic, so interpolation is
+/// done on a logarithmic illuminance scale.
+pub fn curve_percent(curve: &[(f64, f64)], lux: f64) -> f64 {
+    let log_lux = |lux: f64| (lux.max(0.0) + 1.0).log10();
+    let x = log_lux(lux);
+
+    let mut points = curve.iter().map(|&(lux, percent)| (log_lux(lux), percent));
+    let Some(mut previous) = points.next() else {
+        return 100.0;
+    };
+    if x <= previous.0 {
+        return previous.1.clamp(0.0, 100.0);
+    }
+
+    for point in points {
+        if x <= point.0 {
+            let span = point.0 - previous.0;
+            let progress = if span > 0.0 {
+                (x - previous.0) / span
+            } else {
+                1.0
+            };
+            return (previous.1 + (point.1 - previous.1) * progress).clamp(0.0, 100.0);
+        }
+        previous = point;
+    }
+
+    previous.1.clamp(0.0, 100.0)
+}
+
+/// Claim the ambient light sensor from `iio-sensor-proxy`.
+pub async fn claim_light(connection: &zbus::Connection) -> zbus::Result<SensorProxy<'static>> {
+    let proxy = SensorProxy::new(connection).await?;
+    if !proxy.has_ambient_light().await? {
+        return Err(zbus::Error::Failure("no ambient light sensor".into()));
+    }
+    proxy.claim_light().await?;
+    Ok(proxy)
+}
+
+/// Adjust the display brightness to readings from the ambient light sensor
+/// while automatic brightness is enabled.
+pub async fn run(
+    connection: zbus::Connection,
+    sensor_connection: zbus::Connection,
+    events: UnboundedReceiver<Event>,
+    config: brightness::Config,
+) {
+    follow(&sensor_connection, events, config, |percent| {
+        apply(&connection, percent)
+    })
+    .await;
+}
+
+/// Follow the ambient light sensor, calling `set_brightness` with each
+/// brightness percent to apply.
+async fn follow<F, Fut>(
+    sensor_connection: &zbus::Connection,
+    mut events: UnboundedReceiver<Event>,
+    config: brightness::Config,
+    mut set_brightness: F,
+) where
+    F: FnMut(f64) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let mut auto_brightness = AutoBrightness::new(&config);
+    let mut enabled = config.auto_brightness;
+
+    loop {
+        if !enabled {
+            match events.recv().await {
+                Some(Event::Config(config)) => {
+                    auto_brightness.set_config(&config);
+                    enabled = config.auto_brightness;
+                }
+                Some(Event::ManualChange) => (),
+                None => return,
+            }
+            continue;
+        }
+
+        let proxy = match claim_light(sensor_connection).await {
+            Ok(proxy) => proxy,
+            Err(err) => {
+                eprintln!("Failed to claim ambient light sensor: {err}");
+                enabled = false;
+                continue;
+            }
+        };
+
+        let mut light_level = proxy.receive_light_level_changed().await;
+        if let Ok(lux) = proxy.light_level().await {
+            if let Some(percent) = auto_brightness.update(lux) {
+                set_brightness(percent).await;
+            }
+        }
+
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Some(Event::ManualChange) => auto_brightness.pause(),
+                    Some(Event::Config(config)) => {
+                        auto_brightness.set_config(&config);
+                        if !config.auto_brightness {
+                            enabled = false;
+                            _ = proxy.release_light().await;
+                            break;
+                        }
+                    }
+                    None => {
+                        _ = proxy.release_light().await;
+                        return;
+                    }
+                },
+
+                changed = light_level.next() => {
+                    let Some(changed) = changed else {
+                        break;
+                    };
+                    let Ok(lux) = changed.get().await else {
+                        continue;
+                    };
+                    if let Some(percent) = auto_brightness.update(lux) {
+                        set_brightness(percent).await;
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn apply(connection: &zbus::Connection, percent: f64) {
+    let Ok(interface) = connection
+        .object_server()
+        .interface::<_, SettingsDaemon>(DBUS_PATH)
+        .await
+    else {
+        return;
+    };
+
+    let settings_daemon = interface.get().await;
+    settings_daemon
+        .set_automatic_display_brightness(percent)
+        .await;
+    _ = settings_daemon
+        .display_brightness_changed(interface.signal_context())
+        .await;
+    _ = settings_daemon
+        .display_brightness_percent_changed(interface.signal_context())
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::test_util;
+
+    const SENSOR_PATH: &str = "/net/hadess/SensorProxy";
+
+    struct MockSensor {
+        light_level: f64,
+        claimed: bool,
+    }
+
+    #[zbus::interface(name = "net.hadess.SensorProxy")]
+    impl MockSensor {
+        fn claim_light(&mut self) {
+            self.claimed = true;
+        }
+
+        fn release_light(&mut self) {
+            self.claimed = false;
+        }
+
+        #[zbus(property)]
+        fn has_ambient_light(&self) -> bool {
+            true
+        }
+
+        #[zbus(property)]
+        fn light_level(&self) -> f64 {
+            self.light_level
+        }
+
+        #[zbus(property)]
+        fn light_level_unit(&self) -> String {
+            "lux".to_owned()
+        }
+    }
+
+    async fn set_light_level(sensor: &zbus::InterfaceRef<MockSensor>, lux: f64) {
+        let mut mock = sensor.get_mut().await;
+        mock.light_level = lux;
+        mock.light_level_changed(sensor.signal_context())
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn curve_interpolation() {
+        let curve = brightness::Config::default().auto_brightness_curve;
+        assert_eq!(curve_percent(&curve, 0.0), 5.0);
+        assert_eq!(curve_percent(&curve, 1000.0), 70.0);
+        assert_eq!(curve_percent(&curve, 1_000_000.0), 100.0);
+
+        let between = curve_percent(&curve, 300.0);
+        assert!(between > 40.0 && between < 70.0);
+    }
+
+    #[test]
+    fn hysteresis_and_pause() {
+        let mut auto_brightness = AutoBrightness::new(&brightness::Config::default());
+        assert_eq!(auto_brightness.update(1000.0), Some(70.0));
+        assert_eq!(auto_brightness.update(1010.0), None);
+
+        auto_brightness.pause();
+        assert_eq!(auto_brightness.update(10.0), Some(20.0));
+        auto_brightness.pause();
+        assert_eq!(auto_brightness.update(15.0), None);
+    }
+
+    #[tokio::test]
+    async fn follows_mock_sensor_proxy() {
+        let (server, client) = test_util::p2p(
+            SENSOR_PATH,
+            MockSensor {
+                light_level: 0.0,
+                claimed: false,
+            },
+        )
+        .await;
+
+        let proxy = claim_light(&client).await.unwrap();
+        let sensor = server
+            .object_server()
+            .interface::<_, MockSensor>(SENSOR_PATH)
+            .await
+            .unwrap();
+        assert!(sensor.get().await.claimed);
+
+        let mut auto_brightness = AutoBrightness::new(&brightness::Config::default());
+        let mut light_level = proxy.receive_light_level_changed().await;
+
+        set_light_level(&sensor, 1000.0).await;
+
+        loop {
+            let lux = light_level.next().await.unwrap().get().await.unwrap();
+            if lux == 1000.0 {
+                assert_eq!(auto_brightness.update(lux), Some(70.0));
+                break;
+            }
+        }
+
+        proxy.release_light().await.unwrap();
+        assert!(!sensor.get().await.claimed);
+    }
+
+    #[tokio::test]
+    async fn manual_change_pauses_adjustment() {
+        let (server, client) = test_util::p2p(
+            SENSOR_PATH,
+            MockSensor {
+                light_level: 1000.0,
+                claimed: false,
+            },
+        )
+        .await;
+        let sensor = server
+            .object_server()
+            .interface::<_, MockSensor>(SENSOR_PATH)
+            .await
+            .unwrap();
+
+        let (events_tx, events) = mpsc::unbounded_channel();
+        let (applied_tx, mut applied) = mpsc::unbounded_channel();
+        let config = brightness::Config {
+            auto_brightness: true,
+            ..Default::default()
+        };
+        let task = tokio::spawn(async move {
+            follow(&client, events, config, |percent| {
+                _ = applied_tx.send(percent);
+                std::future::ready(())
+            })
+            .await;
+        });
+
+        let timeout = Duration::from_secs(5);
+        let next_applied = tokio::time::timeout(timeout, applied.recv());
+        assert_eq!(next_applied.await.unwrap(), Some(70.0));
+
+        // Within the resume factor of the reading the user adjusted at.
+        events_tx.send(Event::ManualChange).unwrap();
+        set_light_level(&sensor, 600.0).await;
+        let next_applied = tokio::time::timeout(Duration::from_millis(200), applied.recv());
+        assert!(next_applied.await.is_err());
+
+        set_light_level(&sensor, 10.0).await;
+        let next_applied = tokio::time::timeout(timeout, applied.recv());
+        assert_eq!(next_applied.await.unwrap(), Some(20.0));
+
+        drop(events_tx);
+        tokio::time::timeout(timeout, task).await.unwrap().unwrap();
+        assert!(!sensor.get().await.claimed);
+    }
+}
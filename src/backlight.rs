@@ -0,0 +1,136 @@
+// Copyright 2025 System76 <info@system76.com>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use zbus::zvariant::OwnedObjectPath;
+
+use crate::{brightness_device::BrightnessDevice, SettingsDaemon, DBUS_PATH};
+
+/// A single display backlight, served at its own object path.
+pub struct Backlight {
+    pub connection: zbus::Connection,
+    pub device: BrightnessDevice,
+}
+
+#[zbus::interface(name = "com.system76.CosmicSettingsDaemon.Backlight")]
+impl Backlight {
+    #[zbus(property)]
+    async fn brightness(&self) -> i32 {
+        self.device
+            .brightness()
+            .await
+            .ok()
+            .map(|x| x as i32)
+            .unwrap_or(-1)
+    }
+
+    #[zbus(property)]
+    async fn set_brightness(&self, value: i32) {
+        let Ok(daemon) = self
+            .connection
+            .object_server()
+            .interface::<_, SettingsDaemon>(DBUS_PATH)
+            .await
+        else {
+            return;
+        };
+        let ctxt = daemon.signal_context().clone();
+        daemon
+            .get()
+            .await
+            .set_backlight_brightness(&self.device, value, &ctxt)
+            .await;
+    }
+
+    #[zbus(property)]
+    async fn max_brightness(&self) -> i32 {
+        self.device.max_brightness() as i32
+    }
+
+    /// The backlight type: `raw`, `platform`, or `firmware` as reported by sysfs,
+    /// `ddc` for external monitors, or empty if unknown.
+    #[zbus(property, name = "Type")]
+    async fn backlight_type(&self) -> String {
+        self.device
+            .backlight_type()
+            .map(|t| t.as_str().to_owned())
+            .unwrap_or_default()
+    }
+
+    /// The DRM connector driving this backlight, such as `eDP-1`, or empty if unknown.
+    #[zbus(property)]
+    async fn connector(&self) -> String {
+        self.device.connector().unwrap_or_default().to_owned()
+    }
+
+    #[zbus(property)]
+    async fn sys_name(&self) -> String {
+        self.device.sysname().to_owned()
+    }
+}
+
+/// Object path of the backlight with the given sysname.
+pub fn object_path(sysname: &str) -> OwnedObjectPath {
+    let name: String = sysname
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+
+    OwnedObjectPath::try_from(format!("{DBUS_PATH}/Backlight/{name}")).unwrap()
+}
+
+/// Serve a backlight at its object path, returning the path on success.
+pub async fn add(
+    connection: &zbus::Connection,
+    device: BrightnessDevice,
+) -> Option<OwnedObjectPath> {
+    let path = object_path(device.sysname());
+    let sysname = device.sysname().to_owned();
+    let backlight = Backlight {
+        connection: connection.clone(),
+        device,
+    };
+
+    match connection.object_server().at(&path, backlight).await {
+        Ok(_) => Some(path),
+        Err(err) => {
+            eprintln!("Failed to serve backlight {}: {}", sysname, err);
+            None
+        }
+    }
+}
+
+/// Serve the sysfs backlight with the given sysname.
+pub async fn add_sysfs(connection: &zbus::Connection, sysname: &str) -> Option<OwnedObjectPath> {
+    match BrightnessDevice::new("backlight", sysname.to_owned()).await {
+        Ok(device) => add(connection, device).await,
+        Err(err) => {
+            eprintln!("Failed to read max brightness: {}", err);
+            None
+        }
+    }
+}
+
+/// Stop serving the backlight with the given sysname.
+pub async fn remove(connection: &zbus::Connection, sysname: &str) {
+    _ = connection
+        .object_server()
+        .remove::<Backlight, _>(object_path(sysname))
+        .await;
+}
+
+/// Notify clients that the brightness of a backlight changed.
+pub async fn brightness_changed(connection: &zbus::Connection, sysname: &str) {
+    let Ok(backlight) = connection
+        .object_server()
+        .interface::<_, Backlight>(object_path(sysname))
+        .await
+    else {
+        return;
+    };
+
+    _ = backlight
+        .get()
+        .await
+        .brightness_changed(backlight.signal_context())
+        .await;
+}
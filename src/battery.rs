@@ -1,24 +1,205 @@
 use acpid_plug::AcPlugEvents;
+use cosmic_settings_config::power::{self, CriticalAction, WarningMode};
 use notify_rust::Notification;
+use std::collections::HashMap;
+use std::time::Duration;
 use std::time::Instant;
-use std::{path::Path, time::Duration};
 use tokio::sync::mpsc::Sender;
 use tokio::sync::mpsc::{error::TryRecvError, Receiver};
+use tokio::sync::{oneshot, watch};
+use tokio::task::AbortHandle;
 use tokio_stream::StreamExt;
 use upower_dbus::BatteryLevel;
-use zbus::Connection;
+use zbus::{zvariant::OwnedObjectPath, Connection, PropertyStream};
 
-// TODO: Add config parameter for changing the preferred sound theme.
+use crate::{
+    event_sounds::EventSounds,
+    fl,
+    logind_manager::LogindManagerProxy,
+    notifications::NotificationsProxy,
+    power_profiles::{self, ProfileSwitcher},
+    power_status,
+    upower::{DeviceProxy, UPowerProxy, KIND_BATTERY},
+};
 
-pub async fn monitor() {
-    let Ok(ac_plug_events) = acpid_plug::connect().await else {
+/// Notification action enabling the power saver profile.
+const ACTION_POWER_SAVER: &str = "power-saver";
+/// Notification action opening the power settings page.
+const ACTION_SETTINGS: &str = "settings";
+
+/// The low or critical battery notification, replaced in place as the
+/// battery level changes.
+struct BatteryNotification {
+    id: Option<u32>,
+    notifications: Option<NotificationsProxy<'static>>,
+}
+
+impl BatteryNotification {
+    async fn show(
+        &mut self,
+        summary: &str,
+        body: &str,
+        urgency: notify_rust::Urgency,
+        timeout: Duration,
+    ) {
+        let mut notification = Notification::new();
+        notification
+            .appname(&fl!("power"))
+            .summary(summary)
+            .body(body)
+            .icon("dialog-warning-symbolic")
+            .urgency(urgency)
+            .timeout(timeout)
+            .action(ACTION_POWER_SAVER, &fl!("enable-power-saver"))
+            .action(ACTION_SETTINGS, &fl!("open-power-settings"));
+        if let Some(id) = self.id {
+            notification.id(id);
+        }
+
+        match notification.show_async().await {
+            Ok(handle) => self.id = Some(handle.id()),
+            Err(err) => eprintln!("Failed to show battery notification: {err}"),
+        }
+    }
+
+    async fn close(&mut self) {
+        let Some(id) = self.id.take() else {
+            return;
+        };
+        if let Some(notifications) = self.notifications.as_ref() {
+            _ = notifications.close_notification(id).await;
+        }
+    }
+}
+
+/// Handle an action invoked on the battery notification.
+async fn on_battery_notification_action(system_conn: &Connection, action: &str) {
+    match action {
+        ACTION_POWER_SAVER => {
+            let result = async {
+                let power_profiles = power_profiles::connect(system_conn).await?;
+                power_profiles
+                    .set_active_profile(power_profiles::POWER_SAVER)
+                    .await
+            }
+            .await;
+            if let Err(err) = result {
+                eprintln!("Failed to enable power saver: {err}");
+            }
+        }
+        ACTION_SETTINGS => {
+            match tokio::process::Command::new("cosmic-settings")
+                .arg("power")
+                .spawn()
+            {
+                // Reap the process once it exits.
+                Ok(mut child) => {
+                    tokio::task::spawn_local(async move {
+                        _ = child.wait().await;
+                    });
+                }
+                Err(err) => eprintln!("Failed to open power settings: {err}"),
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Monitor the battery and AC plug state, publishing whether AC is plugged in to `ac_plugged_tx`.
+///
+/// The AC state comes from UPower, or from acpid if UPower is unavailable.
+///
+/// The battery status is published on the `Power` interface served by `connection`,
+/// while UPower and logind are reached through `system_conn`.
+pub async fn monitor(
+    connection: Connection,
+    system_conn: Connection,
+    ac_plugged_tx: watch::Sender<bool>,
+    config: watch::Receiver<power::Config>,
+    sounds: EventSounds,
+) {
+    // Kept alive for as long as the monitor runs, even without an AC state source.
+    let (ac_plug_tx, ac_plug_rx) = tokio::sync::mpsc::channel(1);
+
+    let ac_plugged = if let Some((upower, ac_plugged)) = upower_ac_plugged(&system_conn).await {
+        tokio::task::spawn_local(upower_ac_plug_monitor(
+            upower,
+            ac_plugged,
+            ac_plug_tx.clone(),
+        ));
+        ac_plugged
+    } else if let Ok(ac_plug_events) = acpid_plug::connect().await {
+        let ac_plugged = ac_plug_events.plugged();
+        tokio::task::spawn_local(ac_plug_monitor(ac_plug_events, ac_plug_tx.clone()));
+        ac_plugged
+    } else {
+        eprintln!("Failed to get the AC plug state from UPower or acpid");
+        true
+    };
+
+    ac_plugged_tx.send_replace(ac_plugged);
+    let Some(device) = upower_display_device(&system_conn).await else {
         return;
     };
+    low_power_monitor(
+        connection,
+        device,
+        ac_plugged,
+        ac_plug_rx,
+        ac_plugged_tx,
+        config,
+        sounds,
+    )
+    .await;
+}
 
-    let ac_plugged = ac_plug_events.plugged();
-    let (ac_plug_tx, ac_plug_rx) = tokio::sync::mpsc::channel(1);
-    tokio::task::spawn_local(ac_plug_monitor(ac_plug_events, ac_plug_tx));
-    low_power_monitor(ac_plugged, ac_plug_rx).await;
+/// UPower's display device, which combines the batteries powering the system.
+async fn upower_display_device(conn: &Connection) -> Option<DeviceProxy<'static>> {
+    let upower = UPowerProxy::new(conn).await.ok()?;
+    upower.get_display_device().await.ok()
+}
+
+/// Whether AC is plugged in according to UPower, along with the proxy to watch it with.
+async fn upower_ac_plugged(conn: &Connection) -> Option<(UPowerProxy<'static>, bool)> {
+    let upower = UPowerProxy::new(conn).await.ok()?;
+    let on_battery = upower.on_battery().await.ok()?;
+    Some((upower, !on_battery))
+}
+
+/// Watch UPower's `OnBattery` property and send AC plug events when it changes.
+pub async fn upower_ac_plug_monitor(
+    upower: UPowerProxy<'static>,
+    ac_plugged: bool,
+    ac_plug_tx: Sender<acpid_plug::Event>,
+) {
+    let on_battery_changed = upower.receive_on_battery_changed().await;
+    on_battery_plug_events(on_battery_changed, ac_plugged, ac_plug_tx).await;
+}
+
+/// Send AC plug events for the changes of UPower's `OnBattery` property.
+async fn on_battery_plug_events(
+    mut on_battery_changed: PropertyStream<'_, bool>,
+    mut ac_plugged: bool,
+    ac_plug_tx: Sender<acpid_plug::Event>,
+) {
+    while let Some(changed) = on_battery_changed.next().await {
+        let Ok(on_battery) = changed.get().await else {
+            continue;
+        };
+        if ac_plugged != on_battery {
+            continue;
+        }
+
+        ac_plugged = !on_battery;
+        let event = if ac_plugged {
+            acpid_plug::Event::Plugged
+        } else {
+            acpid_plug::Event::Unplugged
+        };
+        if ac_plug_tx.send(event).await.is_err() {
+            break;
+        }
+    }
 }
 
 /// Watch AC plug events and emit sounds on plug event changes.
@@ -50,42 +231,99 @@ pub async fn ac_plug_monitor(
     }
 }
 
-pub async fn low_power_monitor(mut ac_plugged: bool, mut ac_plug_rx: Receiver<acpid_plug::Event>) {
-    let Ok(conn) = Connection::system().await else {
-        return;
+/// Warn about a low battery and take the critical action as the display
+/// `device` drains.
+pub async fn low_power_monitor(
+    session_conn: Connection,
+    device: DeviceProxy<'static>,
+    mut ac_plugged: bool,
+    mut ac_plug_rx: Receiver<acpid_plug::Event>,
+    ac_plugged_tx: watch::Sender<bool>,
+    mut config: watch::Receiver<power::Config>,
+    sounds: EventSounds,
+) {
+    let mut current_battery = BatteryLevel::Full;
+    let mut last_critical_notification = Instant::now();
+    let mut last_low_notification = last_critical_notification;
+    let mut notification = BatteryNotification {
+        id: None,
+        notifications: NotificationsProxy::new(&session_conn).await.ok(),
     };
-
-    let Ok(upower) = upower_dbus::UPowerProxy::new(&conn).await else {
-        return;
+    let mut action_invoked = match notification.notifications.as_ref() {
+        Some(notifications) => notifications.receive_action_invoked().await.ok(),
+        None => None,
     };
+    let mut notification_closed = match notification.notifications.as_ref() {
+        Some(notifications) => notifications.receive_notification_closed().await.ok(),
+        None => None,
+    };
+    let mut percent_changed_stream = device.receive_percentage_changed().await;
+    let mut time_to_empty_changed_stream = device.receive_time_to_empty_changed().await;
+    let mut percent = device.percentage().await.unwrap_or(100.0);
+    let mut time_to_empty = device.time_to_empty().await.unwrap_or(0);
+    let mut time_to_full_changed_stream = device.receive_time_to_full_changed().await;
+    let mut time_to_full = device.time_to_full().await.unwrap_or(0);
 
-    let Ok(device) = upower.get_display_device().await else {
-        return;
+    let mut profile_switcher = match power_profiles::connect(device.inner().connection()).await {
+        Ok(proxy) => Some(ProfileSwitcher::new(proxy)),
+        Err(err) => {
+            eprintln!("Power profile switching is unavailable: {err}");
+            None
+        }
     };
 
-    let mut current_battery = BatteryLevel::Full;
-    let mut last_critical_notification = Instant::now();
-    let mut last_low_notification = last_critical_notification;
-    let mut percent_changed_stream = device.receive_percentage_changed().await;
+    let mut critical_action: Option<CriticalCountdown> = None;
 
     let (nag_tx, nag_rx) = tokio::sync::mpsc::channel(1);
 
-    tokio::task::spawn_local(critical_battery_nag(nag_rx));
+    tokio::task::spawn_local(critical_battery_nag(nag_rx, sounds.clone()));
 
+    // The thresholds are evaluated before the status is first published, so
+    // that it never shows a level not matching the percentage.
+    let mut evaluated = false;
     loop {
+        if evaluated {
+            power_status::publish(
+                &session_conn,
+                power_status::Power {
+                    level: power_status::level_name(current_battery),
+                    ac_plugged,
+                    percentage: percent,
+                    time_to_empty,
+                    time_to_full,
+                },
+            )
+            .await;
+        }
+
         tokio::select! {
+            () = std::future::ready(()), if !evaluated => {}
+
             event = ac_plug_rx.recv() => {
                 let Some(event) = event else {
                     break
                 };
 
                 ac_plugged = event == acpid_plug::Event::Plugged;
+                ac_plugged_tx.send_replace(ac_plugged);
+
+                if ac_plugged {
+                    critical_action = None;
+                    notification.close().await;
+                }
+
+                if let Some(profile_switcher) = profile_switcher.as_mut() {
+                    let config = config.borrow().clone();
+                    profile_switcher.on_ac_plug(ac_plugged, &config).await;
+                }
 
-                on_ac_plug(event, current_battery);
+                on_ac_plug(&sounds, event, current_battery);
 
                 if BatteryLevel::Critical == current_battery {
                     let _res = nag_tx.send(!ac_plugged).await;
                 }
+
+                continue;
             },
 
             result = percent_changed_stream.next() => {
@@ -93,70 +331,244 @@ pub async fn low_power_monitor(mut ac_plugged: bool, mut ac_plug_rx: Receiver<ac
                     break
                 };
 
-                if let Ok(new_percent) = message.get().await {
-                    match new_percent {
-                        percent if percent < 10.0 => {
-                            if current_battery == BatteryLevel::Critical {
-                                continue
-                            }
-
-                            current_battery = BatteryLevel::Critical;
-                            let _res = nag_tx.send(!ac_plugged).await;
-
-                            let now = Instant::now();
-                            if now.duration_since(last_critical_notification) > Duration::from_secs(30) {
-                                last_critical_notification = now;
-                                let _res = Notification::new()
-                                    .appname("")
-                                    .summary("Battery Critical")
-                                    .icon("dialog-warning-symbolic")
-                                    .urgency(notify_rust::Urgency::Critical)
-                                    .timeout(Duration::from_secs(30))
-                                    .show_async()
-                                    .await;
-                            }
-                        }
-
-                        percent if percent < 20.0 => {
-                            if matches!(current_battery, BatteryLevel::Low | BatteryLevel::Critical) {
-                                let _res = nag_tx.send(false).await;
-                                current_battery = BatteryLevel::Low;
-                                continue;
-                            }
-
-                            current_battery = BatteryLevel::Low;
-                            crate::pipewire::play_sound("Pop", "battery-caution");
-
-                            let now = Instant::now();
-                            if now.duration_since(last_low_notification) > Duration::from_secs(5) {
-                                last_low_notification = now;
-                                let _res = Notification::new()
-                                    .appname("")
-                                    .summary("Battery Low")
-                                    .icon("dialog-warning-symbolic")
-                                    .urgency(notify_rust::Urgency::Normal)
-                                    .timeout(Duration::from_secs(5))
-                                    .show_async()
-                                    .await;
-                            }
-
-                        }
-
-                        percent if percent == 100.0 => {
-                            current_battery = BatteryLevel::Full;
-                            crate::pipewire::play_sound("Pop", "battery-full");
-                        }
-
-                        _ => current_battery = BatteryLevel::Normal,
+                let Ok(new_percent) = message.get().await else {
+                    continue;
+                };
+                percent = new_percent;
+            }
+
+            result = time_to_empty_changed_stream.next() => {
+                let Some(message) = result else {
+                    break
+                };
+
+                let Ok(new_time_to_empty) = message.get().await else {
+                    continue;
+                };
+                time_to_empty = new_time_to_empty;
+
+                if config.borrow().warning_mode != WarningMode::TimeRemaining {
+                    continue;
+                }
+            }
+
+            signal = async {
+                match action_invoked.as_mut() {
+                    Some(action_invoked) => action_invoked.next().await,
+                    None => std::future::pending().await,
+                }
+            } => {
+                let Some(signal) = signal else {
+                    action_invoked = None;
+                    continue
+                };
+
+                if let Ok(args) = signal.args() {
+                    if notification.id == Some(args.id) {
+                        on_battery_notification_action(device.inner().connection(), &args.action_key).await;
                     }
                 }
+                continue;
+            }
+
+            signal = async {
+                match notification_closed.as_mut() {
+                    Some(notification_closed) => notification_closed.next().await,
+                    None => std::future::pending().await,
+                }
+            } => {
+                let Some(signal) = signal else {
+                    notification_closed = None;
+                    continue
+                };
+
+                // Expired or dismissed, so not to be shown again by updates.
+                if let Ok(args) = signal.args() {
+                    if notification.id == Some(args.id) {
+                        notification.id = None;
+                    }
+                }
+                continue;
+            }
+
+            result = time_to_full_changed_stream.next() => {
+                let Some(message) = result else {
+                    break
+                };
+
+                if let Ok(new_time_to_full) = message.get().await {
+                    time_to_full = new_time_to_full;
+                }
+                continue;
+            }
+
+            // Thresholds or the critical action may have changed.
+            Ok(()) = config.changed() => {}
+        }
+        evaluated = true;
+
+        // Without batteries, as on desktops, the display device reports 0 %.
+        if !has_battery(&device).await {
+            continue;
+        }
+
+        let config = config.borrow().clone();
+
+        // UPower reports 0 while the estimate is unknown, so fall back to the
+        // percentage thresholds until it has one.
+        let by_time = config.warning_mode == WarningMode::TimeRemaining && time_to_empty > 0;
+        let below = |percentage: f64, secs: u64| {
+            if by_time {
+                time_to_empty < secs as i64
+            } else {
+                percent < percentage
+            }
+        };
+
+        // Once the action was taken, such as suspending, count down again if
+        // the battery is still critical afterwards.
+        if critical_action
+            .as_mut()
+            .is_some_and(|countdown| countdown.taken.try_recv().is_ok())
+        {
+            critical_action = None;
+        }
+
+        let armed = (
+            config.critical_action,
+            Duration::from_secs(config.critical_action_delay_secs),
+        );
+        if ac_plugged
+            || config.critical_action == CriticalAction::Notify
+            || !below(config.action_percentage, config.action_time_secs)
+        {
+            critical_action = None;
+        } else if critical_action
+            .as_ref()
+            .is_none_or(|countdown| countdown.armed != armed)
+        {
+            // Replacing a countdown started with other settings cancels it.
+            let (cancel_tx, cancel_rx) = oneshot::channel();
+            let (taken_tx, taken_rx) = oneshot::channel();
+            critical_action = Some(CriticalCountdown {
+                armed,
+                _cancel: cancel_tx,
+                taken: taken_rx,
+            });
+            tokio::task::spawn_local(critical_action_countdown(
+                device.inner().connection().clone(),
+                armed.0,
+                armed.1,
+                cancel_rx,
+                taken_tx,
+            ));
+        }
+
+        let entering_low = below(config.low_percentage, config.low_time_secs)
+            && !matches!(current_battery, BatteryLevel::Low | BatteryLevel::Critical);
+        if entering_low && !ac_plugged && config.low_battery_power_saver {
+            if let Some(profile_switcher) = profile_switcher.as_mut() {
+                profile_switcher.on_low_battery().await;
+            }
+        }
+
+        if below(config.critical_percentage, config.critical_time_secs) {
+            if current_battery == BatteryLevel::Critical {
+                continue;
+            }
+
+            current_battery = BatteryLevel::Critical;
+            let _res = nag_tx.send(!ac_plugged).await;
+
+            let now = Instant::now();
+            if now.duration_since(last_critical_notification) > Duration::from_secs(30) {
+                last_critical_notification = now;
+                notification
+                    .show(
+                        &fl!("battery-critical"),
+                        &remaining(percent, time_to_empty),
+                        notify_rust::Urgency::Critical,
+                        Duration::from_secs(30),
+                    )
+                    .await;
+            }
+        } else if below(config.low_percentage, config.low_time_secs) {
+            if matches!(current_battery, BatteryLevel::Low | BatteryLevel::Critical) {
+                let _res = nag_tx.send(false).await;
+                current_battery = BatteryLevel::Low;
+
+                // Keep the remaining charge current while the notification is shown.
+                let now = Instant::now();
+                if notification.id.is_some()
+                    && now.duration_since(last_low_notification) > Duration::from_secs(5)
+                {
+                    last_low_notification = now;
+                    notification
+                        .show(
+                            &fl!("battery-low"),
+                            &remaining(percent, time_to_empty),
+                            notify_rust::Urgency::Normal,
+                            Duration::from_secs(5),
+                        )
+                        .await;
+                }
+                continue;
+            }
+
+            current_battery = BatteryLevel::Low;
+            sounds.play("battery-caution");
+
+            let now = Instant::now();
+            if now.duration_since(last_low_notification) > Duration::from_secs(5) {
+                last_low_notification = now;
+                notification
+                    .show(
+                        &fl!("battery-low"),
+                        &remaining(percent, time_to_empty),
+                        notify_rust::Urgency::Normal,
+                        Duration::from_secs(5),
+                    )
+                    .await;
+            }
+        } else if percent == 100.0 {
+            if current_battery == BatteryLevel::Full {
+                continue;
             }
+
+            current_battery = BatteryLevel::Full;
+            notification.close().await;
+            sounds.play("battery-full");
+        } else {
+            current_battery = BatteryLevel::Normal;
+            notification.close().await;
         }
     }
 }
 
+/// Whether a battery is present behind the display device.
+async fn has_battery(device: &DeviceProxy<'_>) -> bool {
+    device.is_present().await.unwrap_or(false) && device.kind().await.ok() == Some(KIND_BATTERY)
+}
+
+/// Describe the remaining charge, including the estimated time if UPower has one.
+fn remaining(percent: f64, time_to_empty: i64) -> String {
+    let percent = format!("{percent:.0}");
+    if time_to_empty <= 0 {
+        return fl!("battery-remaining", percent = percent);
+    }
+
+    let minutes = (time_to_empty + 59) / 60;
+    let (hours, minutes) = (minutes / 60, minutes % 60);
+    fl!(
+        "battery-time-remaining",
+        hours = hours,
+        minutes = minutes,
+        percent = percent
+    )
+}
+
 /// Repeatedly emit critical battery alert until the system begins charging.
-async fn critical_battery_nag(mut watch: Receiver<bool>) {
+async fn critical_battery_nag(mut watch: Receiver<bool>, sounds: EventSounds) {
     loop {
         match watch.recv().await {
             Some(true) => loop {
@@ -167,7 +579,7 @@ async fn critical_battery_nag(mut watch: Receiver<bool>) {
                     _ => break,
                 }
 
-                crate::pipewire::play_sound("Pop", "battery-low");
+                sounds.play("battery-low");
             },
             Some(false) => (),
             None => break,
@@ -175,17 +587,675 @@ async fn critical_battery_nag(mut watch: Receiver<bool>) {
     }
 }
 
+/// A running [`critical_action_countdown`].
+struct CriticalCountdown {
+    /// The action and delay it was started with.
+    armed: (CriticalAction, Duration),
+    /// Dropping it cancels the countdown.
+    _cancel: oneshot::Sender<()>,
+    /// Notified once the action was taken.
+    taken: oneshot::Receiver<()>,
+}
+
+/// Take the critical battery action after `delay`, showing a notification
+/// which counts down to it and allows the user to cancel it.
+///
+/// The countdown is also cancelled when the sender of `cancel` is dropped.
+/// `taken` is notified once the action was taken.
+async fn critical_action_countdown(
+    system_conn: Connection,
+    action: CriticalAction,
+    delay: Duration,
+    cancel: oneshot::Receiver<()>,
+    taken: oneshot::Sender<()>,
+) {
+    let verb = match action {
+        CriticalAction::Notify => return,
+        CriticalAction::Suspend => "suspend",
+        CriticalAction::Hibernate => "hibernate",
+        CriticalAction::PowerOff => "power-off",
+    };
+
+    let notifications = match Connection::session().await {
+        Ok(conn) => NotificationsProxy::new(&conn).await.ok(),
+        Err(_) => None,
+    };
+    let action_invoked = match notifications.as_ref() {
+        Some(notifications) => notifications.receive_action_invoked().await.ok(),
+        None => None,
+    };
+
+    let id = Notification::new()
+        .appname(&fl!("power"))
+        .summary(&fl!("battery-critical"))
+        .body(&fl!(
+            "critical-action-countdown",
+            action = verb,
+            seconds = delay.as_secs()
+        ))
+        .icon("dialog-warning-symbolic")
+        .urgency(notify_rust::Urgency::Critical)
+        .timeout(notify_rust::Timeout::Never)
+        .action("cancel", &fl!("cancel"))
+        .show_async()
+        .await
+        .map(|handle| handle.id())
+        .ok();
+
+    let cancelled_by_user = async {
+        let Some(mut action_invoked) = action_invoked else {
+            return std::future::pending().await;
+        };
+        while let Some(signal) = action_invoked.next().await {
+            if let Ok(args) = signal.args() {
+                if Some(args.id) == id && args.action_key == "cancel" {
+                    return;
+                }
+            }
+        }
+        std::future::pending().await
+    };
+
+    let expired = tokio::select! {
+        _ = tokio::time::sleep(delay) => true,
+        _ = cancelled_by_user => false,
+        _ = cancel => false,
+    };
+
+    if let (Some(notifications), Some(id)) = (notifications.as_ref(), id) {
+        _ = notifications.close_notification(id).await;
+    }
+
+    if expired {
+        perform_critical_action(&system_conn, action, verb).await;
+        _ = taken.send(());
+    }
+}
+
+async fn perform_critical_action(system_conn: &Connection, action: CriticalAction, verb: &str) {
+    let result = async {
+        let manager = LogindManagerProxy::new(system_conn).await?;
+        match action {
+            CriticalAction::Notify => Ok(()),
+            CriticalAction::Suspend => manager.suspend(false).await,
+            CriticalAction::Hibernate => manager.hibernate(false).await,
+            CriticalAction::PowerOff => manager.power_off(false).await,
+        }
+    }
+    .await;
+
+    if let Err(err) = result {
+        eprintln!("Failed to {verb} on critical battery: {err}");
+    }
+}
+
+/// Wireless peripherals with batteries worth warning about, by `UpDeviceKind`.
+#[derive(Clone, Copy, Debug)]
+enum Peripheral {
+    Mouse,
+    Keyboard,
+    GamingInput,
+    Pen,
+    Headset,
+    Headphones,
+}
+
+impl Peripheral {
+    fn from_kind(kind: u32) -> Option<Self> {
+        match kind {
+            5 => Some(Self::Mouse),
+            6 => Some(Self::Keyboard),
+            12 => Some(Self::GamingInput),
+            13 => Some(Self::Pen),
+            17 => Some(Self::Headset),
+            19 => Some(Self::Headphones),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> String {
+        match self {
+            Self::Mouse => fl!("mouse"),
+            Self::Keyboard => fl!("keyboard"),
+            Self::GamingInput => fl!("game-controller"),
+            Self::Pen => fl!("pen"),
+            Self::Headset => fl!("headset"),
+            Self::Headphones => fl!("headphones"),
+        }
+    }
+
+    fn icon(self) -> &'static str {
+        match self {
+            Self::Mouse => "input-mouse-symbolic",
+            Self::Keyboard => "input-keyboard-symbolic",
+            Self::GamingInput => "input-gaming-symbolic",
+            Self::Pen => "input-tablet-symbolic",
+            Self::Headset => "audio-headset-symbolic",
+            Self::Headphones => "audio-headphones-symbolic",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum PeripheralLevel {
+    Normal,
+    Low,
+    Critical,
+}
+
+/// Warn about low batteries of wireless peripherals known to UPower, as they
+/// come and go.
+pub async fn peripheral_monitor(conn: Connection, config: watch::Receiver<power::Config>) {
+    let Ok(upower) = UPowerProxy::new(&conn).await else {
+        return;
+    };
+
+    let (Ok(mut device_added), Ok(mut device_removed)) = (
+        upower.receive_device_added().await,
+        upower.receive_device_removed().await,
+    ) else {
+        return;
+    };
+
+    let mut monitors: HashMap<OwnedObjectPath, AbortHandle> = HashMap::new();
+
+    for path in upower.enumerate_devices().await.unwrap_or_default() {
+        if let Some(monitor) = watch_peripheral(&conn, path.clone(), config.clone()).await {
+            monitors.insert(path, monitor);
+        }
+    }
+
+    loop {
+        tokio::select! {
+            signal = device_added.next() => {
+                let Some(signal) = signal else {
+                    break
+                };
+                let Ok(args) = signal.args() else {
+                    continue
+                };
+
+                let path = OwnedObjectPath::from(args.device);
+                if let Some(monitor) = watch_peripheral(&conn, path.clone(), config.clone()).await {
+                    if let Some(previous) = monitors.insert(path, monitor) {
+                        previous.abort();
+                    }
+                }
+            }
+
+            signal = device_removed.next() => {
+                let Some(signal) = signal else {
+                    break
+                };
+                let Ok(args) = signal.args() else {
+                    continue
+                };
+
+                if let Some(monitor) = monitors.remove(&OwnedObjectPath::from(args.device)) {
+                    monitor.abort();
+                }
+            }
+        }
+    }
+}
+
+/// Start watching the battery of a UPower device, if it is a wireless peripheral.
+async fn watch_peripheral(
+    conn: &Connection,
+    path: OwnedObjectPath,
+    config: watch::Receiver<power::Config>,
+) -> Option<AbortHandle> {
+    let device = DeviceProxy::builder(conn)
+        .path(path)
+        .ok()?
+        .build()
+        .await
+        .ok()?;
+
+    let peripheral = Peripheral::from_kind(device.kind().await.ok()?)?;
+    if device.power_supply().await.unwrap_or(false) {
+        return None;
+    }
+
+    let handle = tokio::task::spawn_local(peripheral_battery_monitor(device, peripheral, config));
+    Some(handle.abort_handle())
+}
+
+/// Notify when a peripheral's battery becomes low or critical, once per level
+/// until it is charged again.
+async fn peripheral_battery_monitor(
+    device: DeviceProxy<'static>,
+    peripheral: Peripheral,
+    config: watch::Receiver<power::Config>,
+) {
+    let name = match device.model().await {
+        Ok(model) if !model.trim().is_empty() => model.trim().to_owned(),
+        _ => peripheral.name(),
+    };
+
+    let mut notified = PeripheralLevel::Normal;
+    let mut percent_changed_stream = device.receive_percentage_changed().await;
+    let mut percent = device.percentage().await.ok();
+
+    loop {
+        if let Some(percent) = percent {
+            let (low, critical) = {
+                let config = config.borrow();
+                (
+                    config.peripheral_low_percentage,
+                    config.peripheral_critical_percentage,
+                )
+            };
+
+            let level = if percent < critical {
+                PeripheralLevel::Critical
+            } else if percent < low {
+                PeripheralLevel::Low
+            } else {
+                PeripheralLevel::Normal
+            };
+
+            if level > notified {
+                notified = level;
+
+                let (summary, urgency) = if level == PeripheralLevel::Critical {
+                    (
+                        fl!("peripheral-battery-critical", device = name.as_str()),
+                        notify_rust::Urgency::Critical,
+                    )
+                } else {
+                    (
+                        fl!("peripheral-battery-low", device = name.as_str()),
+                        notify_rust::Urgency::Normal,
+                    )
+                };
+
+                let _res = Notification::new()
+                    .appname(&fl!("power"))
+                    .summary(&summary)
+                    .body(&fl!("battery-remaining", percent = format!("{percent:.0}")))
+                    .icon(peripheral.icon())
+                    .urgency(urgency)
+                    .timeout(Duration::from_secs(10))
+                    .show_async()
+                    .await;
+            } else if level == PeripheralLevel::Normal {
+                // Warn again once the battery runs low after being charged.
+                notified = level;
+            }
+        }
+
+        let Some(message) = percent_changed_stream.next().await else {
+            break;
+        };
+        percent = message.get().await.ok();
+    }
+}
+
 /// Play a power plug sound on an AC plug event.
-fn on_ac_plug(event: acpid_plug::Event, battery_level: BatteryLevel) {
-    let (theme, sound) = if matches!(event, acpid_plug::Event::Plugged) {
-        ("freedesktop", "power-plug")
-    } else if Path::new("/usr/share/sounds/Pop/").exists()
-        && matches!(battery_level, BatteryLevel::Low | BatteryLevel::Critical)
-    {
-        ("Pop", "power-unplug-battery-low")
+fn on_ac_plug(sounds: &EventSounds, event: acpid_plug::Event, battery_level: BatteryLevel) {
+    // Themes without the low battery variant fall back to `power-unplug`.
+    let event_id = if matches!(event, acpid_plug::Event::Plugged) {
+        "power-plug"
+    } else if matches!(battery_level, BatteryLevel::Low | BatteryLevel::Critical) {
+        "power-unplug-battery-low"
     } else {
-        ("freedesktop", "power-unplug")
+        "power-unplug"
+    };
+
+    sounds.play(event_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+
+    use cosmic_settings_config::sound;
+    use tokio::task::LocalSet;
+    use zbus::object_server::InterfaceRef;
+
+    use super::*;
+    use crate::{
+        playback::{Backend, Player},
+        sound_theme::SoundThemeResolver,
+        test_util::{self, TempDir},
+        DBUS_PATH,
     };
 
-    crate::pipewire::play_sound(theme, sound);
+    const UPOWER_PATH: &str = "/org/freedesktop/UPower";
+    const DISPLAY_DEVICE_PATH: &str = "/org/freedesktop/UPower/devices/DisplayDevice";
+
+    struct MockUPower {
+        on_battery: bool,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.UPower")]
+    impl MockUPower {
+        fn enumerate_devices(&self) -> Vec<OwnedObjectPath> {
+            Vec::new()
+        }
+
+        #[zbus(property)]
+        fn on_battery(&self) -> bool {
+            self.on_battery
+        }
+    }
+
+    async fn set_on_battery(server: &Connection, on_battery: bool) {
+        let upower = server
+            .object_server()
+            .interface::<_, MockUPower>(UPOWER_PATH)
+            .await
+            .unwrap();
+        let mut mock = upower.get_mut().await;
+        mock.on_battery = on_battery;
+        mock.on_battery_changed(upower.signal_context())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn ac_plug_events_from_upower() {
+        let (server, client) = test_util::p2p(UPOWER_PATH, MockUPower { on_battery: false }).await;
+
+        let upower = UPowerProxy::new(&client).await.unwrap();
+        let ac_plugged = !upower.on_battery().await.unwrap();
+        assert!(ac_plugged);
+
+        // Listen for changes before making any, so that none are missed.
+        let on_battery_changed = upower.receive_on_battery_changed().await;
+        let (ac_plug_tx, mut ac_plug_rx) = tokio::sync::mpsc::channel(1);
+        let monitor = tokio::spawn(on_battery_plug_events(
+            on_battery_changed,
+            ac_plugged,
+            ac_plug_tx,
+        ));
+        let timeout = Duration::from_secs(5);
+
+        set_on_battery(&server, true).await;
+        assert!(matches!(
+            tokio::time::timeout(timeout, ac_plug_rx.recv()).await,
+            Ok(Some(acpid_plug::Event::Unplugged))
+        ));
+
+        // Repeated values are not reported as plug events.
+        set_on_battery(&server, true).await;
+        set_on_battery(&server, false).await;
+        assert!(matches!(
+            tokio::time::timeout(timeout, ac_plug_rx.recv()).await,
+            Ok(Some(acpid_plug::Event::Plugged))
+        ));
+
+        monitor.abort();
+    }
+
+    struct MockDevice {
+        is_present: bool,
+        percentage: f64,
+        time_to_empty: i64,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.UPower.Device")]
+    impl MockDevice {
+        /// UPower's display device is of unknown kind without batteries.
+        #[zbus(property, name = "Type")]
+        fn kind(&self) -> u32 {
+            if self.is_present {
+                KIND_BATTERY
+            } else {
+                0
+            }
+        }
+
+        #[zbus(property)]
+        fn is_present(&self) -> bool {
+            self.is_present
+        }
+
+        #[zbus(property)]
+        fn percentage(&self) -> f64 {
+            self.percentage
+        }
+
+        #[zbus(property)]
+        fn time_to_empty(&self) -> i64 {
+            self.time_to_empty
+        }
+
+        #[zbus(property)]
+        fn time_to_full(&self) -> i64 {
+            0
+        }
+    }
+
+    /// A low power monitor of a mock display device, recording the sounds it plays.
+    struct TestMonitor {
+        server: Connection,
+        client: Connection,
+        device: InterfaceRef<MockDevice>,
+        config: watch::Sender<power::Config>,
+        _ac_plug_tx: Sender<acpid_plug::Event>,
+        sounds_dir: TempDir,
+        sounds: Arc<Mutex<Vec<PathBuf>>>,
+        local: LocalSet,
+    }
+
+    impl TestMonitor {
+        async fn new(name: &str, device: MockDevice, config: power::Config) -> Self {
+            let (server, client) = test_util::p2p(DISPLAY_DEVICE_PATH, device).await;
+            client
+                .object_server()
+                .at(DBUS_PATH, power_status::Power::default())
+                .await
+                .unwrap();
+            let mock = server
+                .object_server()
+                .interface::<_, MockDevice>(DISPLAY_DEVICE_PATH)
+                .await
+                .unwrap();
+            let device = DeviceProxy::builder(&client)
+                .path(DISPLAY_DEVICE_PATH)
+                .unwrap()
+                .build()
+                .await
+                .unwrap();
+
+            let sounds_dir = TempDir::new(&format!("battery-{name}"));
+            for sound in ["battery-caution", "battery-full", "battery-low"] {
+                std::fs::write(sounds_dir.path().join(format!("{sound}.oga")), "").unwrap();
+            }
+            let log: Arc<Mutex<Vec<PathBuf>>> = Arc::default();
+            let (_, sound_config) = watch::channel(sound::Config::default());
+            let sounds = EventSounds::with_resolver(
+                sound_config,
+                Player::new(Backend::Null(Some(Arc::clone(&log)))),
+                SoundThemeResolver::with_dirs(vec![sounds_dir.path().to_owned()], ""),
+            );
+
+            let (ac_plug_tx, ac_plug_rx) = tokio::sync::mpsc::channel(1);
+            let (ac_plugged_tx, _) = watch::channel(false);
+            let (config_tx, config) = watch::channel(config);
+
+            let local = LocalSet::new();
+            local.spawn_local(low_power_monitor(
+                client.clone(),
+                device,
+                false,
+                ac_plug_rx,
+                ac_plugged_tx,
+                config,
+                sounds,
+            ));
+
+            Self {
+                server,
+                client,
+                device: mock,
+                config: config_tx,
+                _ac_plug_tx: ac_plug_tx,
+                sounds_dir,
+                sounds: log,
+                local,
+            }
+        }
+
+        async fn level(&self) -> &'static str {
+            let power = self
+                .client
+                .object_server()
+                .interface::<_, power_status::Power>(DBUS_PATH)
+                .await
+                .unwrap();
+            let level = power.get().await.level;
+            level
+        }
+    }
+
+    /// Wait until the low power monitor published the given battery status.
+    async fn published(connection: &Connection, percentage: f64, time_to_empty: i64) {
+        let power = connection
+            .object_server()
+            .interface::<_, power_status::Power>(DBUS_PATH)
+            .await
+            .unwrap();
+        let wait = async {
+            loop {
+                {
+                    let power = power.get().await;
+                    if power.percentage == percentage && power.time_to_empty == time_to_empty {
+                        return;
+                    }
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), wait)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn battery_full_sound_plays_once() {
+        let monitor = TestMonitor::new(
+            "full",
+            MockDevice {
+                is_present: true,
+                percentage: 99.0,
+                time_to_empty: 36000,
+            },
+            power::Config {
+                warning_mode: WarningMode::TimeRemaining,
+                ..Default::default()
+            },
+        )
+        .await;
+        let (client, mock) = (&monitor.client, &monitor.device);
+
+        monitor
+            .local
+            .run_until(async {
+                published(client, 99.0, 36000).await;
+                {
+                    let mut device = mock.get_mut().await;
+                    device.percentage = 100.0;
+                    device
+                        .percentage_changed(mock.signal_context())
+                        .await
+                        .unwrap();
+                }
+                published(client, 100.0, 36000).await;
+
+                for time_to_empty in [36060, 36120, 36180] {
+                    let mut device = mock.get_mut().await;
+                    device.time_to_empty = time_to_empty;
+                    device
+                        .time_to_empty_changed(mock.signal_context())
+                        .await
+                        .unwrap();
+                    drop(device);
+                    published(client, 100.0, time_to_empty).await;
+                }
+            })
+            .await;
+
+        assert_eq!(
+            *monitor.sounds.lock().unwrap(),
+            [monitor.sounds_dir.path().join("battery-full.oga")]
+        );
+    }
+
+    #[tokio::test]
+    async fn publishes_level_of_initial_percentage() {
+        let monitor = TestMonitor::new(
+            "initial",
+            MockDevice {
+                is_present: true,
+                percentage: 40.0,
+                time_to_empty: 0,
+            },
+            power::Config::default(),
+        )
+        .await;
+
+        // Signals of the monitor's client connection arrive at the server.
+        let mut messages = zbus::MessageStream::from(&monitor.server);
+        monitor
+            .local
+            .run_until(published(&monitor.client, 40.0, 0))
+            .await;
+
+        // The published level starts out as `Normal`, so it must not change.
+        let level_changed = async {
+            while let Some(Ok(message)) = messages.next().await {
+                let header = message.header();
+                if header
+                    .member()
+                    .is_some_and(|member| member == "LevelChanged")
+                {
+                    return;
+                }
+            }
+        };
+        assert!(
+            tokio::time::timeout(Duration::from_millis(200), level_changed)
+                .await
+                .is_err()
+        );
+        assert_eq!(
+            monitor.level().await,
+            power_status::level_name(BatteryLevel::Normal)
+        );
+    }
+
+    #[tokio::test]
+    async fn no_warnings_without_battery() {
+        let monitor = TestMonitor::new(
+            "none",
+            MockDevice {
+                is_present: false,
+                percentage: 0.0,
+                time_to_empty: 0,
+            },
+            power::Config::default(),
+        )
+        .await;
+
+        monitor
+            .local
+            .run_until(async {
+                published(&monitor.client, 0.0, 0).await;
+                monitor
+                    .config
+                    .send_modify(|config| config.low_percentage = 30.0);
+                tokio::time::sleep(Duration::from_millis(200)).await;
+            })
+            .await;
+
+        assert_ne!(
+            monitor.level().await,
+            power_status::level_name(BatteryLevel::Critical)
+        );
+        assert!(monitor.sounds.lock().unwrap().is_empty());
+    }
 }
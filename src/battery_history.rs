@@ -0,0 +1,180 @@
+// Copyright 2025 System76 <info@system76.com>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Periodic samples of the system battery, kept in the cosmic state directory
+//! for graphing charge over time and reporting battery wear.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use cosmic_config::{ConfigGet, ConfigSet, CosmicConfigEntry};
+use cosmic_settings_config::power;
+use serde::{Deserialize, Serialize};
+use zbus::{zvariant::Type, Connection};
+
+use crate::upower::{DeviceProxy, UPowerProxy, KIND_BATTERY};
+
+/// State key of the battery history.
+const STATE_KEY: &str = "battery_history";
+
+const INTERVAL: Duration = Duration::from_secs(10 * 60);
+const RETENTION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// A sample of the system battery.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, Type)]
+pub struct Sample {
+    /// Unix time the sample was taken at.
+    pub time: u64,
+    pub percentage: f64,
+    /// Rate the battery is charged or discharged at, in W.
+    pub energy_rate: f64,
+    /// Full energy relative to the design energy, in percent.
+    pub capacity: f64,
+    /// Energy the battery was designed to hold, in Wh.
+    pub energy_full_design: f64,
+}
+
+fn state() -> Result<cosmic_config::Config, cosmic_config::Error> {
+    cosmic_config::Config::new_state(power::ID, power::Config::VERSION)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default()
+}
+
+/// Samples recorded at or after the Unix time `since`.
+pub fn load(since: u64) -> Vec<Sample> {
+    let Ok(helper) = state() else {
+        return Vec::new();
+    };
+
+    let history = helper.get::<Vec<Sample>>(STATE_KEY).unwrap_or_default();
+    recorded_since(history, since)
+}
+
+fn recorded_since(mut history: Vec<Sample>, since: u64) -> Vec<Sample> {
+    history.retain(|sample| sample.time >= since);
+    history
+}
+
+/// Drop samples older than the retention period at the Unix time `now`.
+fn prune(history: &mut Vec<Sample>, now: u64) {
+    let oldest = now.saturating_sub(RETENTION.as_secs());
+    history.retain(|sample| sample.time >= oldest);
+}
+
+/// The battery powering the system, if there is one.
+async fn system_battery(conn: &Connection) -> Option<DeviceProxy<'static>> {
+    let upower = UPowerProxy::new(conn).await.ok()?;
+    for path in upower.enumerate_devices().await.ok()? {
+        let Ok(builder) = DeviceProxy::builder(conn).path(path) else {
+            continue;
+        };
+        let Ok(device) = builder.build().await else {
+            continue;
+        };
+        if device.kind().await.ok() == Some(KIND_BATTERY)
+            && device.power_supply().await.unwrap_or(false)
+        {
+            return Some(device);
+        }
+    }
+    None
+}
+
+async fn sample(device: &DeviceProxy<'_>) -> zbus::Result<Sample> {
+    Ok(Sample {
+        time: now(),
+        percentage: device.percentage().await?,
+        energy_rate: device.energy_rate().await?,
+        capacity: device.capacity().await?,
+        energy_full_design: device.energy_full_design().await?,
+    })
+}
+
+/// Sample the system battery periodically, dropping samples older than the
+/// retention period.
+pub async fn record(conn: Connection) {
+    let Some(device) = system_battery(&conn).await else {
+        return;
+    };
+
+    let helper = match state() {
+        Ok(helper) => helper,
+        Err(err) => {
+            eprintln!("Failed to open battery history state: {err:?}");
+            return;
+        }
+    };
+
+    let mut interval = tokio::time::interval(INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let sample = match sample(&device).await {
+            Ok(sample) => sample,
+            Err(err) => {
+                eprintln!("Failed to sample battery: {err}");
+                continue;
+            }
+        };
+
+        let mut history = helper.get::<Vec<Sample>>(STATE_KEY).unwrap_or_default();
+        prune(&mut history, sample.time);
+        history.push(sample);
+
+        if let Err(err) = helper.set(STATE_KEY, history) {
+            eprintln!("Failed to save battery history: {err:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    fn sample(time: u64) -> Sample {
+        Sample {
+            time,
+            percentage: 50.0,
+            energy_rate: 10.0,
+            capacity: 90.0,
+            energy_full_design: 50.0,
+        }
+    }
+
+    fn times(history: &[Sample]) -> Vec<u64> {
+        history.iter().map(|sample| sample.time).collect()
+    }
+
+    #[test]
+    fn prunes_samples_older_than_30_days() {
+        let now = 100 * DAY;
+        let mut history = vec![
+            sample(now - 31 * DAY),
+            sample(now - 30 * DAY - 1),
+            sample(now - 30 * DAY),
+            sample(now - DAY),
+        ];
+        prune(&mut history, now);
+        assert_eq!(times(&history), [now - 30 * DAY, now - DAY]);
+
+        // Clocks set before the retention period keep everything.
+        let mut history = vec![sample(0), sample(DAY)];
+        prune(&mut history, 2 * DAY);
+        assert_eq!(times(&history), [0, DAY]);
+    }
+
+    #[test]
+    fn loads_samples_since_inclusive() {
+        let history = vec![sample(100), sample(200), sample(300)];
+        assert_eq!(times(&recorded_since(history.clone(), 0)), [100, 200, 300]);
+        assert_eq!(times(&recorded_since(history.clone(), 200)), [200, 300]);
+        assert_eq!(times(&recorded_since(history.clone(), 201)), [300]);
+        assert!(recorded_since(history, 301).is_empty());
+    }
+}
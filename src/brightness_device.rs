@@ -1,37 +1,241 @@
 use std::{error::Error, io, str::FromStr};
 use tokio::fs;
 
-use crate::LogindSessionProxy;
+use crate::{ddc::DdcBacklight, LogindSessionProxy};
+
+/// Exponent of the curve mapping perceived brightness onto backlight levels.
+///
+/// Backlights are roughly linear in luminance, while perception is closer to
+/// logarithmic, so equal steps in raw levels look tiny at the top of the range
+/// and huge at the bottom.
+const PERCEPTUAL_GAMMA: f64 = 2.2;
+
+/// Size of a single brightness key press, in perceptual percent.
+const PERCEPTUAL_STEP: f64 = 5.0;
 
 fn invalid_data<E: Error + Send + Sync + 'static>(err: E) -> io::Error {
     io::Error::new(io::ErrorKind::InvalidData, err)
 }
 
-pub struct BrightnessDevice {
+/// Backlight control type, as reported by the sysfs `type` attribute.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BacklightType {
+    /// Controlled by writing directly to the graphics card registers.
+    Raw,
+    /// Controlled through a platform-specific interface.
+    Platform,
+    /// Controlled through a standard firmware interface, such as ACPI.
+    Firmware,
+    /// An external monitor controlled over DDC/CI.
+    Ddc,
+}
+
+impl BacklightType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Raw => "raw",
+            Self::Platform => "platform",
+            Self::Firmware => "firmware",
+            Self::Ddc => "ddc",
+        }
+    }
+}
+
+impl FromStr for BacklightType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "raw" => Ok(Self::Raw),
+            "platform" => Ok(Self::Platform),
+            "firmware" => Ok(Self::Firmware),
+            _ => Err(()),
+        }
+    }
+}
+
+/// What caused a brightness change.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BrightnessSource {
+    /// A brightness key was pressed.
+    Keybinding,
+    /// Another application set the brightness over D-Bus.
+    Client,
+    /// The brightness changed outside of the daemon, such as by firmware hotkeys.
+    Hardware,
+    /// Automatic brightness adjusted to the ambient light.
+    Automatic,
+}
+
+impl BrightnessSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Keybinding => "keybinding",
+            Self::Client => "client",
+            Self::Hardware => "hardware",
+            Self::Automatic => "automatic",
+        }
+    }
+}
+
+/// A device whose brightness can be read and changed.
+pub trait BrightnessBackend {
+    fn max_brightness(&self) -> u32;
+
+    async fn brightness(&self) -> io::Result<u32>;
+
+    async fn set_brightness(
+        &self,
+        logind_session: &LogindSessionProxy<'_>,
+        value: u32,
+    ) -> zbus::Result<()>;
+}
+
+/// A backlight or LED exposed in sysfs, written through logind.
+#[derive(Clone)]
+pub struct SysfsBacklight {
     subsystem: &'static str,
     sysname: String,
     max_brightness: u32,
+    backlight_type: Option<BacklightType>,
+    connector: Option<String>,
 }
 
-impl BrightnessDevice {
+impl SysfsBacklight {
     pub async fn new(subsystem: &'static str, sysname: String) -> io::Result<Self> {
         let path = format!("/sys/class/{}/{}/max_brightness", subsystem, &sysname);
         let value = fs::read_to_string(&path).await?;
         let max_brightness = u32::from_str(value.trim()).map_err(invalid_data)?;
+
+        let path = format!("/sys/class/{}/{}/type", subsystem, &sysname);
+        let backlight_type = fs::read_to_string(&path)
+            .await
+            .ok()
+            .and_then(|value| BacklightType::from_str(value.trim()).ok());
+
+        let connector = Self::read_connector(subsystem, &sysname).await;
+
         Ok(Self {
             subsystem,
             sysname,
             max_brightness,
+            backlight_type,
+            connector,
         })
     }
-    pub async fn brightness(&self) -> io::Result<u32> {
+
+    /// DRM backlights are children of their connector, such as `card1-eDP-1`.
+    async fn read_connector(subsystem: &str, sysname: &str) -> Option<String> {
+        let path = format!("/sys/class/{}/{}/device", subsystem, sysname);
+        let parent = fs::canonicalize(&path).await.ok()?;
+        let parent = parent.file_name()?.to_str()?;
+        let (card, connector) = parent.split_once('-')?;
+        card.starts_with("card").then(|| connector.to_owned())
+    }
+}
+
+impl BrightnessBackend for SysfsBacklight {
+    fn max_brightness(&self) -> u32 {
+        self.max_brightness
+    }
+
+    async fn brightness(&self) -> io::Result<u32> {
         let path = format!("/sys/class/{}/{}/brightness", self.subsystem, &self.sysname);
         let value = fs::read_to_string(&path).await?;
         Ok(u32::from_str(value.trim()).map_err(invalid_data)?)
     }
 
+    async fn set_brightness(
+        &self,
+        logind_session: &LogindSessionProxy<'_>,
+        value: u32,
+    ) -> zbus::Result<()> {
+        logind_session
+            .set_brightness(self.subsystem, &self.sysname, value)
+            .await
+    }
+}
+
+#[derive(Clone)]
+pub enum BrightnessDevice {
+    Sysfs(SysfsBacklight),
+    Ddc(DdcBacklight),
+}
+
+impl BrightnessDevice {
+    pub async fn new(subsystem: &'static str, sysname: String) -> io::Result<Self> {
+        SysfsBacklight::new(subsystem, sysname)
+            .await
+            .map(Self::Sysfs)
+    }
+
+    /// Probe an external monitor over DDC/CI on the I2C bus with the given sysname.
+    pub async fn new_ddc(
+        sysname: &str,
+        connector: Option<String>,
+        edid: Option<Vec<u8>>,
+    ) -> io::Result<Self> {
+        DdcBacklight::open(sysname, connector, edid)
+            .await
+            .map(Self::Ddc)
+    }
+
+    pub async fn brightness(&self) -> io::Result<u32> {
+        match self {
+            Self::Sysfs(device) => device.brightness().await,
+            Self::Ddc(device) => device.brightness().await,
+        }
+    }
+
     pub fn max_brightness(&self) -> u32 {
-        self.max_brightness
+        match self {
+            Self::Sysfs(device) => device.max_brightness(),
+            Self::Ddc(device) => device.max_brightness(),
+        }
+    }
+
+    pub fn sysname(&self) -> &str {
+        match self {
+            Self::Sysfs(device) => &device.sysname,
+            Self::Ddc(device) => device.name(),
+        }
+    }
+
+    /// Key under which the brightness chosen for the device is saved.
+    ///
+    /// I2C bus numbers are not stable across boots and hotplugs, so external
+    /// monitors are identified by their EDID, or else their connector.
+    pub fn state_key(&self) -> String {
+        match self {
+            Self::Sysfs(device) => device.sysname.clone(),
+            Self::Ddc(device) => match (device.edid_id(), device.connector()) {
+                (Some(edid_id), _) => format!("ddc:{edid_id}"),
+                (None, Some(connector)) => format!("ddc:{connector}"),
+                (None, None) => device.name().to_owned(),
+            },
+        }
+    }
+
+    pub fn backlight_type(&self) -> Option<BacklightType> {
+        match self {
+            Self::Sysfs(device) => device.backlight_type,
+            Self::Ddc(_) => Some(BacklightType::Ddc),
+        }
+    }
+
+    /// Whether brightness changes can be faded in many small steps.
+    ///
+    /// DDC/CI writes take longer than a fade frame and flood the monitor's
+    /// control channel, so external monitors are set directly.
+    pub fn can_fade(&self) -> bool {
+        matches!(self, Self::Sysfs(_))
+    }
+
+    pub fn connector(&self) -> Option<&str> {
+        match self {
+            Self::Sysfs(device) => device.connector.as_deref(),
+            Self::Ddc(device) => device.connector(),
+        }
     }
 
     pub async fn set_brightness(
@@ -39,14 +243,48 @@ impl BrightnessDevice {
         logind_session: &LogindSessionProxy<'_>,
         value: u32,
     ) -> zbus::Result<()> {
-        logind_session
-            .set_brightness(self.subsystem, &self.sysname, value)
-            .await
+        match self {
+            Self::Sysfs(device) => device.set_brightness(logind_session, value).await,
+            Self::Ddc(device) => device.set_brightness(logind_session, value).await,
+        }
     }
 
     // Matches definition used in gnome-settings-daemon, which seems to work
     // well enough.
     pub fn brightness_step(&self) -> u32 {
-        (self.max_brightness / 20).max(1)
+        (self.max_brightness() / 20).max(1)
+    }
+
+    /// Convert a raw brightness level into perceived brightness, from 0 to 100.
+    pub fn brightness_to_percent(&self, value: u32) -> f64 {
+        let max_brightness = self.max_brightness();
+        if max_brightness == 0 {
+            return 0.0;
+        }
+        let linear = f64::from(value.min(max_brightness)) / f64::from(max_brightness);
+        linear.powf(PERCEPTUAL_GAMMA.recip()) * 100.0
+    }
+
+    /// Convert perceived brightness, from 0 to 100, into a raw brightness level.
+    pub fn percent_to_brightness(&self, percent: f64) -> u32 {
+        let perceived = (percent / 100.0).clamp(0.0, 1.0);
+        (perceived.powf(PERCEPTUAL_GAMMA) * f64::from(self.max_brightness())).round() as u32
+    }
+
+    /// Raw brightness level one perceptual step above or below `value`.
+    ///
+    /// Always moves by at least one raw level, so that devices with few levels
+    /// still respond to every key press.
+    pub fn perceptual_step(&self, value: u32, increase: bool) -> u32 {
+        let percent = self.brightness_to_percent(value);
+        if increase {
+            let target = self.percent_to_brightness(percent + PERCEPTUAL_STEP);
+            target
+                .max(value.saturating_add(1))
+                .min(self.max_brightness())
+        } else {
+            let target = self.percent_to_brightness(percent - PERCEPTUAL_STEP);
+            target.min(value.saturating_sub(1))
+        }
     }
 }
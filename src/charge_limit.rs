@@ -0,0 +1,290 @@
+// Copyright 2025 System76 <info@system76.com>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Battery charge thresholds, which preserve battery health by keeping it
+//! from charging fully.
+//!
+//! Writing the thresholds requires root, so they are applied by running this
+//! binary as a helper through `pkexec`.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use cosmic_settings_config::power;
+
+pub const SYSFS_ROOT: &str = "/sys";
+
+/// Command line argument running the binary as the charge threshold helper.
+pub const HELPER_COMMAND: &str = "set-charge-thresholds";
+
+/// Power config keys affecting the charge thresholds.
+pub const CONFIG_KEYS: &[&str] = &[
+    "preserve_battery_health",
+    "charge_start_threshold",
+    "charge_end_threshold",
+];
+
+const START_THRESHOLD: &str = "charge_control_start_threshold";
+const END_THRESHOLD: &str = "charge_control_end_threshold";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Thresholds {
+    /// Percentage below which charging starts.
+    pub start: u8,
+    /// Percentage at which charging stops.
+    pub end: u8,
+}
+
+impl Thresholds {
+    /// Charge whenever the battery is not full.
+    pub const FULL: Self = Self { start: 0, end: 100 };
+
+    /// Thresholds to apply for the power config.
+    pub fn from_config(config: &power::Config) -> Self {
+        if config.preserve_battery_health {
+            Self {
+                start: config.charge_start_threshold,
+                end: config.charge_end_threshold,
+            }
+        } else {
+            Self::FULL
+        }
+    }
+
+    pub fn is_valid(self) -> bool {
+        self.start < self.end && self.end <= 100
+    }
+}
+
+/// Batteries under the sysfs `root` supporting charge thresholds.
+pub fn batteries(root: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(root.join("class/power_supply")) else {
+        return Vec::new();
+    };
+
+    let mut batteries: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            let attribute = |name| fs::read_to_string(path.join(name)).unwrap_or_default();
+            attribute("type").trim() == "Battery"
+                // Batteries of peripherals are also reported, with a `Device` scope.
+                && attribute("scope").trim() != "Device"
+                && path.join(END_THRESHOLD).exists()
+        })
+        .collect();
+    batteries.sort();
+    batteries
+}
+
+fn read_threshold(battery: &Path, name: &str) -> io::Result<u8> {
+    fs::read_to_string(battery.join(name))?
+        .trim()
+        .parse()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Current thresholds of a battery. Batteries with only an end threshold
+/// report a start threshold of 0.
+pub fn read(battery: &Path) -> io::Result<Thresholds> {
+    let end = read_threshold(battery, END_THRESHOLD)?;
+    let start = match read_threshold(battery, START_THRESHOLD) {
+        Ok(start) => start,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => 0,
+        Err(err) => return Err(err),
+    };
+    Ok(Thresholds { start, end })
+}
+
+/// Set the thresholds of a battery.
+pub fn write(battery: &Path, thresholds: Thresholds) -> io::Result<()> {
+    let has_start = battery.join(START_THRESHOLD).exists();
+    let write_start = || {
+        if has_start {
+            fs::write(battery.join(START_THRESHOLD), thresholds.start.to_string())
+        } else {
+            Ok(())
+        }
+    };
+    let write_end = || fs::write(battery.join(END_THRESHOLD), thresholds.end.to_string());
+
+    // The kernel rejects a start threshold above the end threshold, so the
+    // order depends on which way they move.
+    let current_end = read_threshold(battery, END_THRESHOLD).unwrap_or(100);
+    if thresholds.start >= current_end {
+        write_end()?;
+        write_start()
+    } else {
+        write_start()?;
+        write_end()
+    }
+}
+
+/// Set the thresholds of every battery under the sysfs `root`.
+pub fn write_all(root: &Path, thresholds: Thresholds) -> io::Result<()> {
+    for battery in batteries(root) {
+        write(&battery, thresholds)?;
+    }
+    Ok(())
+}
+
+/// Run the helper with the arguments following [`HELPER_COMMAND`], returning
+/// the exit code.
+pub fn helper(mut args: impl Iterator<Item = String>) -> i32 {
+    let mut threshold = || args.next().and_then(|arg| arg.parse::<u8>().ok());
+    let (Some(start), Some(end)) = (threshold(), threshold()) else {
+        eprintln!("Usage: {HELPER_COMMAND} START END");
+        return 2;
+    };
+
+    let thresholds = Thresholds { start, end };
+    if !thresholds.is_valid() {
+        eprintln!("Invalid charge thresholds: {start} to {end}");
+        return 2;
+    }
+
+    match write_all(Path::new(SYSFS_ROOT), thresholds) {
+        Ok(()) => 0,
+        Err(err) => {
+            eprintln!("Failed to set charge thresholds: {err}");
+            1
+        }
+    }
+}
+
+/// Whether every battery already has the charge thresholds for the power config.
+pub fn is_applied(config: &power::Config) -> bool {
+    let thresholds = Thresholds::from_config(config);
+    batteries(Path::new(SYSFS_ROOT))
+        .iter()
+        .all(|battery| read(battery).is_ok_and(|current| current == thresholds))
+}
+
+/// Apply the thresholds for the power config through the helper, unless every
+/// battery already has them.
+pub async fn apply(config: &power::Config) -> io::Result<()> {
+    let thresholds = Thresholds::from_config(config);
+    if !thresholds.is_valid() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "invalid charge thresholds: {} to {}",
+                thresholds.start, thresholds.end
+            ),
+        ));
+    }
+
+    if is_applied(config) {
+        return Ok(());
+    }
+
+    // Dropping the future, when a newer config supersedes this one, dismisses
+    // the authentication prompt.
+    let status = tokio::process::Command::new("pkexec")
+        .arg(std::env::current_exe()?)
+        .arg(HELPER_COMMAND)
+        .arg(thresholds.start.to_string())
+        .arg(thresholds.end.to_string())
+        .kill_on_drop(true)
+        .status()
+        .await?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("helper failed with {status}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    /// A fake sysfs tree.
+    struct FakeSysfs(TempDir);
+
+    impl FakeSysfs {
+        fn new(name: &str) -> Self {
+            let dir = TempDir::new(&format!("sysfs-{name}"));
+            fs::create_dir_all(dir.path().join("class/power_supply")).unwrap();
+            Self(dir)
+        }
+
+        fn root(&self) -> &Path {
+            self.0.path()
+        }
+
+        fn power_supply(&self, name: &str, attributes: &[(&str, &str)]) -> PathBuf {
+            let path = self.root().join("class/power_supply").join(name);
+            fs::create_dir_all(&path).unwrap();
+            for (attribute, value) in attributes {
+                fs::write(path.join(attribute), format!("{value}\n")).unwrap();
+            }
+            path
+        }
+    }
+
+    #[test]
+    fn finds_system_batteries_with_thresholds() {
+        let sysfs = FakeSysfs::new("batteries");
+        sysfs.power_supply("AC", &[("type", "Mains")]);
+        let bat0 = sysfs.power_supply(
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("scope", "System"),
+                (START_THRESHOLD, "0"),
+                (END_THRESHOLD, "100"),
+            ],
+        );
+        sysfs.power_supply("BAT1", &[("type", "Battery")]);
+        sysfs.power_supply(
+            "hidpp_battery_0",
+            &[
+                ("type", "Battery"),
+                ("scope", "Device"),
+                (END_THRESHOLD, "100"),
+            ],
+        );
+
+        assert_eq!(batteries(sysfs.root()), vec![bat0.clone()]);
+        assert_eq!(read(&bat0).unwrap(), Thresholds::FULL);
+    }
+
+    #[test]
+    fn writes_thresholds() {
+        let sysfs = FakeSysfs::new("write");
+        let bat0 = sysfs.power_supply(
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                (START_THRESHOLD, "0"),
+                (END_THRESHOLD, "100"),
+            ],
+        );
+        let bat1 = sysfs.power_supply("BAT1", &[("type", "Battery"), (END_THRESHOLD, "100")]);
+
+        let limited = Thresholds { start: 75, end: 80 };
+        write_all(sysfs.root(), limited).unwrap();
+        assert_eq!(read(&bat0).unwrap(), limited);
+        assert_eq!(read(&bat1).unwrap(), Thresholds { start: 0, end: 80 });
+
+        write_all(sysfs.root(), Thresholds { start: 85, end: 90 }).unwrap();
+        assert_eq!(read(&bat0).unwrap(), Thresholds { start: 85, end: 90 });
+    }
+
+    #[test]
+    fn thresholds_from_config() {
+        let mut config = power::Config::default();
+        assert_eq!(Thresholds::from_config(&config), Thresholds::FULL);
+
+        config.preserve_battery_health = true;
+        let thresholds = Thresholds::from_config(&config);
+        assert!(thresholds.is_valid());
+        assert_eq!(thresholds.end, config.charge_end_threshold);
+
+        assert!(!Thresholds { start: 80, end: 80 }.is_valid());
+    }
+}
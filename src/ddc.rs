@@ -0,0 +1,468 @@
+// Copyright 2025 System76 <info@system76.com>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Monitor brightness control over DDC/CI.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read, Write},
+    os::fd::AsRawFd,
+    path::Path,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crate::{brightness_device::BrightnessBackend, LogindSessionProxy};
+
+/// VCP feature code of the display luminance.
+pub const VCP_BRIGHTNESS: u8 = 0x10;
+
+/// 7-bit I2C address of the DDC/CI display.
+const DDC_ADDRESS: u16 = 0x37;
+/// 7-bit I2C address of the display's EDID EEPROM.
+const EDID_ADDRESS: u16 = 0x50;
+/// Size of the base EDID block.
+const EDID_LENGTH: usize = 128;
+/// Fixed header of every EDID.
+const EDID_HEADER: [u8; 8] = [0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00];
+/// Source address used by the host in requests.
+const HOST_ADDRESS: u8 = 0x51;
+/// Destination address used in request checksums.
+const DISPLAY_WRITE_ADDRESS: u8 = 0x6E;
+/// Virtual host address used in reply checksums.
+const REPLY_CHECKSUM_ADDRESS: u8 = 0x50;
+
+const GET_VCP_REQUEST: u8 = 0x01;
+const GET_VCP_REPLY: u8 = 0x02;
+const SET_VCP_REQUEST: u8 = 0x03;
+
+/// Time the display needs to prepare a reply, per the DDC/CI specification.
+const REPLY_DELAY: Duration = Duration::from_millis(40);
+/// Time the display needs to process a set request.
+const SET_DELAY: Duration = Duration::from_millis(50);
+
+/// `ioctl` request selecting the address of the I2C peripheral.
+const I2C_SLAVE: libc::c_ulong = 0x0703;
+
+/// Raw access to an I2C bus.
+pub trait I2cBus: Send {
+    fn write(&mut self, address: u16, data: &[u8]) -> io::Result<()>;
+
+    fn read(&mut self, address: u16, buf: &mut [u8]) -> io::Result<()>;
+}
+
+impl<B: I2cBus + ?Sized> I2cBus for Box<B> {
+    fn write(&mut self, address: u16, data: &[u8]) -> io::Result<()> {
+        (**self).write(address, data)
+    }
+
+    fn read(&mut self, address: u16, buf: &mut [u8]) -> io::Result<()> {
+        (**self).read(address, buf)
+    }
+}
+
+/// An I2C bus exposed by the kernel at `/dev/i2c-*`.
+pub struct I2cDev {
+    file: File,
+}
+
+impl I2cDev {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        Ok(Self { file })
+    }
+
+    fn set_address(&self, address: u16) -> io::Result<()> {
+        // SAFETY: `I2C_SLAVE` takes an integer argument and the descriptor is open.
+        let res = unsafe {
+            libc::ioctl(
+                self.file.as_raw_fd(),
+                I2C_SLAVE as _,
+                libc::c_ulong::from(address),
+            )
+        };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+impl I2cBus for I2cDev {
+    fn write(&mut self, address: u16, data: &[u8]) -> io::Result<()> {
+        self.set_address(address)?;
+        self.file.write_all(data)
+    }
+
+    fn read(&mut self, address: u16, buf: &mut [u8]) -> io::Result<()> {
+        self.set_address(address)?;
+        self.file.read_exact(buf)
+    }
+}
+
+fn checksum(initial: u8, bytes: &[u8]) -> u8 {
+    bytes.iter().fold(initial, |acc, byte| acc ^ byte)
+}
+
+fn protocol_error(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// A display speaking DDC/CI over an I2C bus.
+pub struct Ddc<B> {
+    bus: B,
+}
+
+impl<B: I2cBus> Ddc<B> {
+    pub fn new(bus: B) -> Self {
+        Self { bus }
+    }
+
+    /// Read a VCP feature, returning its current and maximum values.
+    pub fn get_vcp(&mut self, code: u8) -> io::Result<(u16, u16)> {
+        let mut request = [HOST_ADDRESS, 0x82, GET_VCP_REQUEST, code, 0];
+        request[4] = checksum(DISPLAY_WRITE_ADDRESS, &request[..4]);
+        self.bus.write(DDC_ADDRESS, &request)?;
+
+        thread::sleep(REPLY_DELAY);
+
+        let mut reply = [0u8; 11];
+        self.bus.read(DDC_ADDRESS, &mut reply)?;
+
+        if checksum(REPLY_CHECKSUM_ADDRESS, &reply[..10]) != reply[10] {
+            return Err(protocol_error("DDC/CI reply checksum mismatch"));
+        }
+        if reply[1] != 0x88 || reply[2] != GET_VCP_REPLY || reply[4] != code {
+            return Err(protocol_error("unexpected DDC/CI reply"));
+        }
+        if reply[3] != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "VCP feature not supported by display",
+            ));
+        }
+
+        let max = u16::from_be_bytes([reply[6], reply[7]]);
+        let current = u16::from_be_bytes([reply[8], reply[9]]);
+        Ok((current, max))
+    }
+
+    /// Set the value of a VCP feature.
+    pub fn set_vcp(&mut self, code: u8, value: u16) -> io::Result<()> {
+        let [high, low] = value.to_be_bytes();
+        let mut request = [HOST_ADDRESS, 0x84, SET_VCP_REQUEST, code, high, low, 0];
+        request[6] = checksum(DISPLAY_WRITE_ADDRESS, &request[..6]);
+        self.bus.write(DDC_ADDRESS, &request)?;
+
+        thread::sleep(SET_DELAY);
+        Ok(())
+    }
+}
+
+/// Read the base EDID block of the display on an I2C bus.
+pub fn read_edid(bus: &mut impl I2cBus) -> io::Result<Vec<u8>> {
+    bus.write(EDID_ADDRESS, &[0])?;
+    let mut edid = vec![0; EDID_LENGTH];
+    bus.read(EDID_ADDRESS, &mut edid)?;
+    Ok(edid)
+}
+
+/// Identify a display by the manufacturer, product code and serial number in
+/// its EDID, such as `DEL-A0F4-4C4B5A30`.
+pub fn edid_id(edid: &[u8]) -> Option<String> {
+    if edid.len() < EDID_LENGTH || edid[..8] != EDID_HEADER {
+        return None;
+    }
+
+    let vendor = u16::from_be_bytes([edid[8], edid[9]]);
+    let manufacturer = [10, 5, 0]
+        .into_iter()
+        .map(|shift| match (vendor >> shift) & 0x1F {
+            letter @ 1..=26 => Some(char::from(b'A' + letter as u8 - 1)),
+            _ => None,
+        })
+        .collect::<Option<String>>()?;
+    let product = u16::from_le_bytes([edid[10], edid[11]]);
+    let serial = u32::from_le_bytes([edid[12], edid[13], edid[14], edid[15]]);
+    Some(format!("{manufacturer}-{product:04X}-{serial:08X}"))
+}
+
+/// Brightness of an external monitor, controlled over DDC/CI.
+#[derive(Clone)]
+pub struct DdcBacklight {
+    name: String,
+    connector: Option<String>,
+    /// Identity of the display from its EDID, if it could be read.
+    edid_id: Option<String>,
+    max_brightness: u32,
+    ddc: Arc<Mutex<Ddc<Box<dyn I2cBus>>>>,
+}
+
+impl DdcBacklight {
+    /// Probe a bus for a display supporting brightness control.
+    pub async fn new(
+        name: String,
+        connector: Option<String>,
+        edid_id: Option<String>,
+        bus: Box<dyn I2cBus>,
+    ) -> io::Result<Self> {
+        let ddc = Arc::new(Mutex::new(Ddc::new(bus)));
+        let (_, max) = Self::blocking(ddc.clone(), |ddc| ddc.get_vcp(VCP_BRIGHTNESS)).await?;
+        if max == 0 {
+            return Err(protocol_error("display reports no brightness range"));
+        }
+
+        Ok(Self {
+            name,
+            connector,
+            edid_id,
+            max_brightness: u32::from(max),
+            ddc,
+        })
+    }
+
+    /// Probe the kernel I2C bus with the given sysname, such as `i2c-5`.
+    ///
+    /// The EDID is read from the bus if the connector did not provide it.
+    pub async fn open(
+        sysname: &str,
+        connector: Option<String>,
+        edid: Option<Vec<u8>>,
+    ) -> io::Result<Self> {
+        let mut bus = I2cDev::open(&Path::new("/dev").join(sysname))?;
+        let (bus, edid) = match edid {
+            Some(edid) => (bus, Some(edid)),
+            None => tokio::task::spawn_blocking(move || {
+                let edid = read_edid(&mut bus).ok();
+                (bus, edid)
+            })
+            .await
+            .map_err(io::Error::other)?,
+        };
+        let edid_id = edid.as_deref().and_then(edid_id);
+        Self::new(sysname.to_owned(), connector, edid_id, Box::new(bus)).await
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn connector(&self) -> Option<&str> {
+        self.connector.as_deref()
+    }
+
+    pub fn edid_id(&self) -> Option<&str> {
+        self.edid_id.as_deref()
+    }
+
+    async fn blocking<T: Send + 'static>(
+        ddc: Arc<Mutex<Ddc<Box<dyn I2cBus>>>>,
+        f: impl FnOnce(&mut Ddc<Box<dyn I2cBus>>) -> io::Result<T> + Send + 'static,
+    ) -> io::Result<T> {
+        tokio::task::spawn_blocking(move || f(&mut ddc.lock().unwrap()))
+            .await
+            .map_err(io::Error::other)?
+    }
+}
+
+impl BrightnessBackend for DdcBacklight {
+    fn max_brightness(&self) -> u32 {
+        self.max_brightness
+    }
+
+    async fn brightness(&self) -> io::Result<u32> {
+        let (current, _) =
+            Self::blocking(self.ddc.clone(), |ddc| ddc.get_vcp(VCP_BRIGHTNESS)).await?;
+        Ok(u32::from(current))
+    }
+
+    async fn set_brightness(
+        &self,
+        _logind_session: &LogindSessionProxy<'_>,
+        value: u32,
+    ) -> zbus::Result<()> {
+        let value = value.min(self.max_brightness) as u16;
+        Self::blocking(self.ddc.clone(), move |ddc| {
+            ddc.set_vcp(VCP_BRIGHTNESS, value)
+        })
+        .await
+        .map_err(|err| zbus::Error::InputOutput(Arc::new(err)))
+    }
+}
+
+/// DRM connector device of an `i2c-dev` device, if its adapter belongs to one.
+///
+/// DDC buses of graphics cards are children of their connector, such as `card1-DP-2`.
+fn connector_device(device: &udev::Device) -> Option<(udev::Device, String)> {
+    let adapter = device.parent()?;
+    let parent = adapter.parent()?;
+    let (card, connector) = parent.sysname().to_str()?.split_once('-')?;
+    let connector = connector.to_owned();
+    card.starts_with("card").then_some((parent, connector))
+}
+
+/// DRM connector of an `i2c-dev` device, if its adapter belongs to one.
+pub fn connector(device: &udev::Device) -> Option<String> {
+    connector_device(device).map(|(_, connector)| connector)
+}
+
+/// Whether the connector of an `i2c-dev` device has a display connected.
+///
+/// Buses without a connector are assumed to have one.
+pub fn is_connected(device: &udev::Device) -> bool {
+    connector_device(device).is_none_or(|(connector, _)| {
+        connector
+            .attribute_value("status")
+            .is_none_or(|status| status != "disconnected")
+    })
+}
+
+/// EDID of the display on the connector of an `i2c-dev` device, if any.
+pub fn edid(device: &udev::Device) -> Option<Vec<u8>> {
+    let (connector, _) = connector_device(device)?;
+    std::fs::read(connector.syspath().join("edid"))
+        .ok()
+        .filter(|edid| !edid.is_empty())
+}
+
+/// Whether an `i2c-dev` device is likely to reach an external display.
+///
+/// Only buses of a DRM connector, or adapters named as DDC buses by their
+/// driver, are probed, since probing motherboard SMBus or sensor buses may
+/// confuse other devices. Internal panels are skipped too, since they are
+/// driven by a backlight.
+pub fn is_display_bus(device: &udev::Device) -> bool {
+    if let Some(connector) = connector(device) {
+        return !(connector.starts_with("eDP-") || connector.starts_with("LVDS-"));
+    }
+
+    device.parent().is_some_and(|adapter| {
+        adapter
+            .attribute_value("name")
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.to_ascii_lowercase().contains("ddc"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A display implementing the DDC/CI VCP get and set requests in memory.
+    #[derive(Default)]
+    struct FakeDisplay {
+        brightness: u16,
+        reply: Vec<u8>,
+    }
+
+    impl I2cBus for Arc<Mutex<FakeDisplay>> {
+        fn write(&mut self, address: u16, data: &[u8]) -> io::Result<()> {
+            assert_eq!(address, DDC_ADDRESS);
+            let (last, payload) = data.split_last().unwrap();
+            assert_eq!(checksum(DISPLAY_WRITE_ADDRESS, payload), *last);
+
+            let mut display = self.lock().unwrap();
+            match payload {
+                [HOST_ADDRESS, 0x82, GET_VCP_REQUEST, code] => {
+                    let (result, max, current) = if *code == VCP_BRIGHTNESS {
+                        (0, 100u16, display.brightness)
+                    } else {
+                        (1, 0, 0)
+                    };
+                    let [max_high, max_low] = max.to_be_bytes();
+                    let [high, low] = current.to_be_bytes();
+                    let mut reply = vec![
+                        DISPLAY_WRITE_ADDRESS,
+                        0x88,
+                        GET_VCP_REPLY,
+                        result,
+                        *code,
+                        0,
+                        max_high,
+                        max_low,
+                        high,
+                        low,
+                    ];
+                    reply.push(checksum(REPLY_CHECKSUM_ADDRESS, &reply));
+                    display.reply = reply;
+                }
+                [HOST_ADDRESS, 0x84, SET_VCP_REQUEST, VCP_BRIGHTNESS, high, low] => {
+                    display.brightness = u16::from_be_bytes([*high, *low]);
+                }
+                _ => panic!("unexpected request {data:x?}"),
+            }
+            Ok(())
+        }
+
+        fn read(&mut self, address: u16, buf: &mut [u8]) -> io::Result<()> {
+            assert_eq!(address, DDC_ADDRESS);
+            let display = self.lock().unwrap();
+            buf.copy_from_slice(&display.reply);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn get_and_set_brightness() {
+        let display = Arc::new(Mutex::new(FakeDisplay {
+            brightness: 30,
+            ..Default::default()
+        }));
+        let mut ddc = Ddc::new(display.clone());
+
+        assert_eq!(ddc.get_vcp(VCP_BRIGHTNESS).unwrap(), (30, 100));
+        ddc.set_vcp(VCP_BRIGHTNESS, 75).unwrap();
+        assert_eq!(display.lock().unwrap().brightness, 75);
+        assert_eq!(ddc.get_vcp(VCP_BRIGHTNESS).unwrap(), (75, 100));
+
+        let err = ddc.get_vcp(0x12).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn rejects_corrupt_reply() {
+        struct Corrupt;
+
+        impl I2cBus for Corrupt {
+            fn write(&mut self, _: u16, _: &[u8]) -> io::Result<()> {
+                Ok(())
+            }
+
+            fn read(&mut self, _: u16, buf: &mut [u8]) -> io::Result<()> {
+                buf.fill(0xFF);
+                Ok(())
+            }
+        }
+
+        let err = Ddc::new(Corrupt).get_vcp(VCP_BRIGHTNESS).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn identifies_display_by_edid() {
+        let mut edid = vec![0; EDID_LENGTH];
+        edid[..8].copy_from_slice(&EDID_HEADER);
+        edid[8..16].copy_from_slice(&[0x10, 0xAC, 0xF4, 0xA0, 0x30, 0x5A, 0x4B, 0x4C]);
+        assert_eq!(edid_id(&edid).as_deref(), Some("DEL-A0F4-4C4B5A30"));
+
+        assert_eq!(edid_id(&edid[..64]), None);
+        edid[0] = 0xFF;
+        assert_eq!(edid_id(&edid), None);
+    }
+
+    #[tokio::test]
+    async fn backlight_over_fake_display() {
+        let display = Arc::new(Mutex::new(FakeDisplay {
+            brightness: 10,
+            ..Default::default()
+        }));
+        let backlight =
+            DdcBacklight::new("i2c-test".to_owned(), None, None, Box::new(display.clone()))
+                .await
+                .unwrap();
+
+        assert_eq!(backlight.max_brightness(), 100);
+        assert_eq!(backlight.brightness().await.unwrap(), 10);
+    }
+}
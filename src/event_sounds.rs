@@ -0,0 +1,275 @@
+// Copyright 2023 System76 <info@system76.com>
+// SPDX-License-Identifier: MPL-2.0
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use cosmic_settings_config::sound;
+use tokio::sync::watch;
+
+use crate::playback::{Playback, Player};
+use crate::sound_theme::SoundThemeResolver;
+
+/// Plays event sounds from the configured sound theme, unless disabled.
+#[derive(Clone)]
+pub struct EventSounds {
+    config: watch::Receiver<sound::Config>,
+    player: Player,
+    paths: Arc<Mutex<SoundPaths>>,
+}
+
+/// Sound files found for each theme and event ID, for the sound config
+/// they were looked up with.
+struct SoundPaths {
+    resolver: SoundThemeResolver,
+    config: sound::Config,
+    paths: HashMap<(String, String), Option<PathBuf>>,
+}
+
+impl EventSounds {
+    pub fn new(config: watch::Receiver<sound::Config>, player: Player) -> Self {
+        Self::with_resolver(config, player, SoundThemeResolver::new())
+    }
+
+    /// Look up themed sounds with the given resolver.
+    pub fn with_resolver(
+        config: watch::Receiver<sound::Config>,
+        player: Player,
+        resolver: SoundThemeResolver,
+    ) -> Self {
+        let paths = SoundPaths {
+            resolver,
+            config: config.borrow().clone(),
+            paths: HashMap::new(),
+        };
+        Self {
+            config,
+            player,
+            paths: Arc::new(Mutex::new(paths)),
+        }
+    }
+
+    /// Play the sound for an XDG sound naming spec event ID.
+    pub fn play(&self, event_id: &str) {
+        _ = self.play_with_properties(event_id, &HashMap::new());
+    }
+
+    /// Play the sound for an event ID with libcanberra style properties.
+    /// Nothing is played if the event's sound is disabled or missing.
+    ///
+    /// `media.filename` plays a file from the sound directories instead of a
+    /// themed sound,
+    /// `canberra.xdg-theme.name` overrides the sound theme and
+    /// `canberra.volume` adjusts the volume in dB. All properties are also
+    /// set on the playback stream.
+    pub fn play_with_properties(
+        &self,
+        event_id: &str,
+        properties: &HashMap<String, String>,
+    ) -> Option<Playback> {
+        let config = self.config.borrow();
+        if !config.event_enabled(event_id) {
+            return None;
+        }
+
+        let path = match properties.get("media.filename") {
+            Some(filename) => self
+                .paths
+                .lock()
+                .unwrap()
+                .resolver
+                .sound_file(Path::new(filename))?,
+            None => {
+                let theme = properties
+                    .get("canberra.xdg-theme.name")
+                    .unwrap_or(&config.theme);
+                self.sound_path(&config, theme, event_id)?
+            }
+        };
+
+        let gain = properties
+            .get("canberra.volume")
+            .and_then(|db| db.parse::<f32>().ok())
+            .filter(|db| db.is_finite())
+            .map_or(1.0, |db| 10f32.powf(db / 20.0));
+
+        let mut properties = properties.clone();
+        properties
+            .entry("event.id".to_owned())
+            .or_insert_with(|| event_id.to_owned());
+        properties
+            .entry("media.role".to_owned())
+            .or_insert_with(|| "event".to_owned());
+
+        self.player.play(&path, config.volume * gain, &properties)
+    }
+
+    /// Find the sound file for an event ID in a theme, caching the result
+    /// until the sound config changes.
+    fn sound_path(&self, config: &sound::Config, theme: &str, event_id: &str) -> Option<PathBuf> {
+        let mut paths = self.paths.lock().unwrap();
+        if paths.config != *config {
+            paths.config = config.clone();
+            paths.paths.clear();
+        }
+
+        let SoundPaths {
+            resolver, paths, ..
+        } = &mut *paths;
+        paths
+            .entry((theme.to_owned(), event_id.to_owned()))
+            .or_insert_with(|| resolver.lookup(theme, event_id))
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::playback::Backend;
+    use crate::test_util::TempDir;
+
+    /// Sound directories containing the given files, and event sounds
+    /// searching them.
+    fn event_sounds(
+        name: &str,
+        config: sound::Config,
+        files: &[&str],
+    ) -> (TempDir, EventSounds, Arc<Mutex<Vec<PathBuf>>>) {
+        let dir = TempDir::new(name);
+        let sounds = dir.path().join("sounds");
+        fs::create_dir_all(&sounds).unwrap();
+        for file in files {
+            fs::write(sounds.join(file), "").unwrap();
+        }
+
+        let log = Arc::default();
+        let player = Player::new(Backend::Null(Some(Arc::clone(&log))));
+        let (_, config) = watch::channel(config);
+        let resolver = SoundThemeResolver::with_dirs(vec![sounds], "");
+        let sounds = EventSounds::with_resolver(config, player, resolver);
+        (dir, sounds, log)
+    }
+
+    fn file(path: &Path) -> HashMap<String, String> {
+        HashMap::from([(
+            "media.filename".to_owned(),
+            path.to_string_lossy().into_owned(),
+        )])
+    }
+
+    #[test]
+    fn plays_enabled_events() {
+        let (dir, sounds, log) = event_sounds(
+            "enabled-events",
+            sound::Config {
+                battery_full_sound: false,
+                ..Default::default()
+            },
+            &["plug.oga", "full.oga", "bell.oga"],
+        );
+        let sound = |name| dir.path().join("sounds").join(name);
+
+        assert!(sounds
+            .play_with_properties("power-plug", &file(&sound("plug.oga")))
+            .is_some());
+        assert!(sounds
+            .play_with_properties("battery-full", &file(&sound("full.oga")))
+            .is_none());
+        assert!(sounds
+            .play_with_properties("bell", &file(&sound("bell.oga")))
+            .is_some());
+
+        assert_eq!(*log.lock().unwrap(), [sound("plug.oga"), sound("bell.oga")]);
+    }
+
+    #[test]
+    fn global_switch_mutes_all_events() {
+        let (dir, sounds, log) = event_sounds(
+            "global-switch",
+            sound::Config {
+                event_sounds: false,
+                ..Default::default()
+            },
+            &["plug.oga", "bell.oga"],
+        );
+        let sound = |name| dir.path().join("sounds").join(name);
+
+        assert!(sounds
+            .play_with_properties("power-plug", &file(&sound("plug.oga")))
+            .is_none());
+        assert!(sounds
+            .play_with_properties("bell", &file(&sound("bell.oga")))
+            .is_none());
+        assert!(log.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn only_plays_files_in_sound_dirs() {
+        let (dir, sounds, log) =
+            event_sounds("sound-dirs", sound::Config::default(), &["bell.oga"]);
+        fs::write(dir.path().join("secret"), "").unwrap();
+
+        assert!(sounds
+            .play_with_properties("bell", &file(&dir.path().join("secret")))
+            .is_none());
+        assert!(sounds
+            .play_with_properties("bell", &file(&dir.path().join("sounds/../secret")))
+            .is_none());
+        assert!(sounds
+            .play_with_properties("bell", &file(Path::new("/etc/passwd")))
+            .is_none());
+        assert!(log.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn only_looks_up_sounds_in_sound_dirs() {
+        let (dir, sounds, log) = event_sounds("sound-names", sound::Config::default(), &[]);
+        fs::write(dir.path().join("secret.oga"), "").unwrap();
+        let theme = dir.path().join("theme");
+        fs::create_dir_all(&theme).unwrap();
+        fs::write(theme.join("index.theme"), "[Sound Theme]\n").unwrap();
+        fs::write(theme.join("bell.oga"), "").unwrap();
+
+        assert!(sounds
+            .play_with_properties("../secret", &HashMap::new())
+            .is_none());
+        let properties =
+            HashMap::from([("canberra.xdg-theme.name".to_owned(), "../theme".to_owned())]);
+        assert!(sounds.play_with_properties("bell", &properties).is_none());
+        assert!(log.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn looks_up_sounds_again_when_config_changes() {
+        let dir = TempDir::new("event-sounds");
+        let (user, system) = (dir.path().join("user"), dir.path().join("system"));
+        fs::create_dir_all(&user).unwrap();
+        fs::create_dir_all(&system).unwrap();
+        fs::write(system.join("bell.oga"), "").unwrap();
+
+        let log = Arc::default();
+        let player = Player::new(Backend::Null(Some(Arc::clone(&log))));
+        let (config_tx, config) = watch::channel(sound::Config::default());
+        let resolver = SoundThemeResolver::with_dirs(vec![user.clone(), system.clone()], "");
+        let sounds = EventSounds::with_resolver(config, player, resolver);
+
+        sounds.play("bell");
+        fs::write(user.join("bell.oga"), "").unwrap();
+        sounds.play("bell");
+        config_tx.send_modify(|config| config.volume = 0.5);
+        sounds.play("bell");
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                system.join("bell.oga"),
+                system.join("bell.oga"),
+                user.join("bell.oga")
+            ]
+        );
+    }
+}
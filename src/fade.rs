@@ -0,0 +1,83 @@
+// Copyright 2025 System76 <info@system76.com>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::task::AbortHandle;
+
+use crate::{brightness_device::BrightnessDevice, LogindSessionProxy};
+
+/// Interval between brightness updates while fading, roughly one per frame.
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Animates brightness changes of a device, cancelling any fade still in
+/// progress when a new target arrives.
+#[derive(Default)]
+pub struct Fader {
+    fade: Mutex<Option<(AbortHandle, u32)>>,
+}
+
+impl Fader {
+    /// Target of the fade in progress, if any.
+    pub fn target(&self) -> Option<u32> {
+        let fade = self.fade.lock().unwrap();
+        fade.as_ref()
+            .filter(|(handle, _)| !handle.is_finished())
+            .map(|(_, target)| *target)
+    }
+
+    /// Stop the fade in progress, leaving the brightness where it is.
+    pub fn cancel(&self) {
+        if let Some((handle, _)) = self.fade.lock().unwrap().take() {
+            handle.abort();
+        }
+    }
+
+    /// Fade from the current brightness to `target` over `duration`.
+    ///
+    /// Steps are spaced evenly on the perceptual scale so that the fade
+    /// appears linear.
+    pub fn fade(
+        &self,
+        logind_session: LogindSessionProxy<'static>,
+        device: BrightnessDevice,
+        target: u32,
+        duration: Duration,
+    ) {
+        let mut fade = self.fade.lock().unwrap();
+        if let Some((previous, _)) = fade.take() {
+            previous.abort();
+        }
+
+        let handle = tokio::spawn(async move {
+            let Ok(start) = device.brightness().await else {
+                _ = device.set_brightness(&logind_session, target).await;
+                return;
+            };
+
+            let start_percent = device.brightness_to_percent(start);
+            let target_percent = device.brightness_to_percent(target);
+            let frames = (duration.as_millis() / FRAME_INTERVAL.as_millis()).max(1) as u32;
+            let mut current = start;
+
+            for frame in 1..frames {
+                let progress = f64::from(frame) / f64::from(frames);
+                let percent = start_percent + (target_percent - start_percent) * progress;
+                let value = device.percent_to_brightness(percent);
+                if value != current {
+                    current = value;
+                    if let Err(err) = device.set_brightness(&logind_session, value).await {
+                        eprintln!("Failed to set brightness during fade: {err}");
+                        return;
+                    }
+                }
+                tokio::time::sleep(FRAME_INTERVAL).await;
+            }
+
+            _ = device.set_brightness(&logind_session, target).await;
+        });
+
+        *fade = Some((handle.abort_handle(), target));
+    }
+}
@@ -0,0 +1,113 @@
+// Copyright 2025 System76 <info@system76.com>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Translations of user-facing strings, such as notifications.
+
+use std::sync::LazyLock;
+
+use i18n_embed::{
+    fluent::{fluent_language_loader, FluentLanguageLoader},
+    unic_langid::LanguageIdentifier,
+    DefaultLocalizer, LanguageLoader, Localizer,
+};
+use rust_embed::RustEmbed;
+
+#[derive(RustEmbed)]
+#[folder = "i18n/"]
+struct Localizations;
+
+pub static LANGUAGE_LOADER: LazyLock<FluentLanguageLoader> = LazyLock::new(|| {
+    let loader: FluentLanguageLoader = fluent_language_loader!();
+    loader
+        .load_fallback_language(&Localizations)
+        .expect("Error while loading fallback language");
+    loader.set_use_isolating(false);
+    loader
+});
+
+/// Translate a message by its ID, with optional arguments.
+#[macro_export]
+macro_rules! fl {
+    ($message_id:literal) => {{
+        i18n_embed_fl::fl!($crate::localize::LANGUAGE_LOADER, $message_id)
+    }};
+
+    ($message_id:literal, $($args:expr),*) => {{
+        i18n_embed_fl::fl!($crate::localize::LANGUAGE_LOADER, $message_id, $($args), *)
+    }};
+}
+
+pub fn localizer() -> Box<dyn Localizer> {
+    Box::from(DefaultLocalizer::new(&*LANGUAGE_LOADER, &Localizations))
+}
+
+/// Select the translations for the most preferred of `languages` which are
+/// available, falling back to English for missing messages.
+pub fn select(languages: &[LanguageIdentifier]) {
+    if let Err(err) = localizer().select(languages) {
+        eprintln!("Error while loading translations: {err}");
+    }
+
+    // Notification servers do not handle bidi isolation marks around
+    // arguments, and loading languages resets the setting.
+    LANGUAGE_LOADER.set_use_isolating(false);
+}
+
+/// Select the translations for the languages of the session locale.
+pub fn localize() {
+    select(&i18n_embed::DesktopLanguageRequester::requested_languages());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use i18n_embed::I18nAssets;
+
+    use super::*;
+
+    #[derive(RustEmbed)]
+    #[folder = "tests/i18n/"]
+    struct PartialLocalizations;
+
+    /// The shipped translations along with a partial French one.
+    struct TestLocalizations;
+
+    impl I18nAssets for TestLocalizations {
+        fn get_files(&self, file_path: &str) -> Vec<Cow<'_, [u8]>> {
+            let mut files = Localizations.get_files(file_path);
+            files.extend(PartialLocalizations.get_files(file_path));
+            files
+        }
+
+        fn filenames_iter(&self) -> Box<dyn Iterator<Item = String> + '_> {
+            Box::new(
+                Localizations
+                    .filenames_iter()
+                    .chain(PartialLocalizations.filenames_iter()),
+            )
+        }
+    }
+
+    #[test]
+    fn missing_messages_fall_back_to_english() {
+        let loader: FluentLanguageLoader = fluent_language_loader!();
+        loader
+            .load_languages(&TestLocalizations, &["fr".parse().unwrap()])
+            .unwrap();
+        loader.set_use_isolating(false);
+
+        assert_eq!(loader.get("battery-low"), "Batterie faible");
+        assert_eq!(loader.get("battery-critical"), "Battery Critical");
+        assert_eq!(
+            i18n_embed_fl::fl!(
+                loader,
+                "battery-time-remaining",
+                hours = 1,
+                minutes = 5,
+                percent = "42"
+            ),
+            "About 1 h 5 min remaining (42%)"
+        );
+    }
+}
@@ -0,0 +1,12 @@
+#[zbus::proxy(
+    default_service = "org.freedesktop.login1",
+    interface = "org.freedesktop.login1.Manager",
+    default_path = "/org/freedesktop/login1"
+)]
+trait LogindManager {
+    fn suspend(&self, interactive: bool) -> zbus::Result<()>;
+
+    fn hibernate(&self, interactive: bool) -> zbus::Result<()>;
+
+    fn power_off(&self, interactive: bool) -> zbus::Result<()>;
+}
@@ -1,15 +1,17 @@
 // Copyright 2023 System76 <info@system76.com>
 // SPDX-License-Identifier: GPL-3.0-only
 
-use brightness_device::BrightnessDevice;
+use brightness_device::{BrightnessDevice, BrightnessSource};
+use cosmic_config::{ConfigGet, ConfigSet, CosmicConfigEntry};
+use cosmic_settings_config::{brightness, power, sound};
 use logind_session::LogindSessionProxy;
 use notify::{event::ModifyKind, EventKind, Watcher};
 use std::sync::atomic::AtomicU64;
 use std::time::Duration;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     io,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{atomic::Ordering, Arc},
 };
 use theme::watch_theme;
@@ -21,21 +23,39 @@ use tokio::{
 use tokio_stream::StreamExt;
 use zbus::{
     names::{MemberName, UniqueName, WellKnownName},
-    zvariant::ObjectPath,
+    zvariant::{ObjectPath, OwnedObjectPath},
     Connection, MatchRule, MessageStream, SignalContext,
 };
+mod auto_brightness;
+mod backlight;
 mod battery;
+mod battery_history;
 mod brightness_device;
+mod charge_limit;
+mod ddc;
+mod event_sounds;
+mod fade;
 mod input;
 mod locale;
+mod localize;
+mod logind_manager;
 mod logind_session;
-mod pipewire;
+mod notifications;
+mod playback;
+mod power_profiles;
+mod power_status;
+mod sensor_proxy;
+mod sound_service;
+mod sound_theme;
+#[cfg(test)]
+mod test_util;
 mod theme;
+mod upower;
+mod volume;
 
 // Use seperate HasDisplayBrightness, or -1?
 // Is it fair to assume a display device will notify on change?
 // TODO: notifications; statusnotifierwatcher, media keybindings
-// Scale brightness to 0 to 100? Or something else? Float?
 
 pub static ID_COUNTER: AtomicU64 = AtomicU64::new(0);
 const GEOCLUE_AGENT: Option<&'static str> = option_env!("GEOCLUE_AGENT");
@@ -43,9 +63,44 @@ const GEOCLUE_AGENT: Option<&'static str> = option_env!("GEOCLUE_AGENT");
 static DBUS_NAME: &str = "com.system76.CosmicSettingsDaemon";
 static DBUS_PATH: &str = "/com/system76/CosmicSettingsDaemon";
 
+/// State key holding the last display brightness chosen for each backlight,
+/// by [`BrightnessDevice::state_key`].
+const DISPLAY_BRIGHTNESS_STATE_KEY: &str = "display_brightness";
+/// Like [`DISPLAY_BRIGHTNESS_STATE_KEY`], for battery power when power source
+/// profiles are enabled.
+const DISPLAY_BRIGHTNESS_BATTERY_STATE_KEY: &str = "display_brightness_battery";
+
+/// A brightness change to announce with the `BrightnessChanged` signal.
+struct BrightnessChange {
+    device: &'static str,
+    value: u32,
+    max: u32,
+    source: BrightnessSource,
+}
+
 struct SettingsDaemon {
     logind_session: Option<LogindSessionProxy<'static>>,
     display_brightness_device: Option<BrightnessDevice>,
+    keyboard_brightness_device: Option<BrightnessDevice>,
+    /// External monitors controllable over DDC/CI, used as the display
+    /// brightness device when there is no backlight.
+    ddc_monitors: Vec<BrightnessDevice>,
+    backlights: BTreeMap<String, OwnedObjectPath>,
+    brightness_config: brightness::Config,
+    brightness_config_helper: Option<cosmic_config::Config>,
+    brightness_state_helper: Option<cosmic_config::Config>,
+    display_fader: fade::Fader,
+    on_battery: bool,
+    /// Last display and keyboard brightness set or announced by the daemon,
+    /// used to tell changes made outside of it apart.
+    display_brightness_written: std::sync::Mutex<Option<u32>>,
+    keyboard_brightness_written: std::sync::Mutex<Option<u32>>,
+    brightness_change_tx: tokio::sync::mpsc::UnboundedSender<BrightnessChange>,
+    auto_brightness_tx: tokio::sync::mpsc::UnboundedSender<auto_brightness::Event>,
+    power_config_helper: Option<cosmic_config::Config>,
+    power_config_tx: tokio::sync::watch::Sender<power::Config>,
+    sound_config_helper: Option<cosmic_config::Config>,
+    sound_config_tx: tokio::sync::watch::Sender<sound::Config>,
     watched_configs: Arc<
         RwLock<HashMap<(String, u64), (Connection, ObjectPath<'static>, WellKnownName<'static>)>>,
     >,
@@ -136,6 +191,21 @@ impl SettingsDaemon {
         }
     }
 
+    /// Object paths of every display backlight.
+    async fn list_backlights(&self) -> Vec<OwnedObjectPath> {
+        self.backlights.values().cloned().collect()
+    }
+
+    /// Object path of the backlight controlled by `DisplayBrightness`, or `/` if there is none.
+    #[zbus(property)]
+    async fn primary_backlight(&self) -> OwnedObjectPath {
+        self.display_brightness_device
+            .as_ref()
+            .and_then(|device| self.backlights.get(device.sysname()))
+            .cloned()
+            .unwrap_or_else(|| OwnedObjectPath::try_from("/").unwrap())
+    }
+
     /// Take the current xkb config and switch the active input source.
     async fn input_source_switch(&self) {
         if let Err(why) = input::source_switch() {
@@ -154,37 +224,95 @@ impl SettingsDaemon {
 
     #[zbus(property)]
     async fn set_display_brightness(&self, value: i32) {
-        if let Some(logind_session) = self.logind_session.as_ref() {
-            if let Some(brightness_device) = self.display_brightness_device.as_ref() {
-                _ = brightness_device
-                    .set_brightness(logind_session, value as u32)
-                    .await;
-            }
+        self.set_display_brightness_from(value, BrightnessSource::Client)
+            .await;
+    }
+
+    /// Set the display brightness without enforcing the minimum brightness,
+    /// allowing the backlight to be turned off entirely.
+    async fn set_display_brightness_allow_off(
+        &self,
+        value: i32,
+        #[zbus(signal_context)] ctxt: zbus::SignalContext<'_>,
+    ) {
+        _ = self
+            .auto_brightness_tx
+            .send(auto_brightness::Event::ManualChange);
+        let value = value.max(0) as u32;
+        self.apply_display_brightness(value).await;
+        if let Some(brightness_device) = self.display_brightness_device.as_ref() {
+            let value = value.min(brightness_device.max_brightness());
+            self.announce_brightness(
+                "display",
+                brightness_device,
+                value,
+                BrightnessSource::Client,
+            );
+        }
+        _ = self.display_brightness_changed(&ctxt).await;
+        _ = self.display_brightness_percent_changed(&ctxt).await;
+    }
+
+    /// Display brightness on a perceptual scale from 0 to 100, or -1 if there is no backlight.
+    #[zbus(property)]
+    async fn display_brightness_percent(&self) -> f64 {
+        let Some(brightness_device) = self.display_brightness_device.as_ref() else {
+            return -1.0;
+        };
+        match brightness_device.brightness().await {
+            Ok(value) => brightness_device.brightness_to_percent(value),
+            Err(_) => -1.0,
+        }
+    }
+
+    #[zbus(property)]
+    async fn set_display_brightness_percent(&self, value: f64) {
+        if let Some(brightness_device) = self.display_brightness_device.as_ref() {
+            let value = brightness_device.percent_to_brightness(value);
+            self.set_display_brightness(value as i32).await;
         }
     }
 
     #[zbus(property)]
     async fn keyboard_brightness(&self) -> i32 {
-        -1
+        if let Some(brightness_device) = self.keyboard_brightness_device.as_ref() {
+            brightness_device
+                .brightness()
+                .await
+                .ok()
+                .map(|x| x as i32)
+                .unwrap_or(-1)
+        } else {
+            -1
+        }
+    }
+
+    #[zbus(property)]
+    async fn max_keyboard_brightness(&self) -> i32 {
+        if let Some(brightness_device) = self.keyboard_brightness_device.as_ref() {
+            brightness_device.max_brightness() as i32
+        } else {
+            -1
+        }
     }
 
     #[zbus(property)]
-    async fn set_keyboard_brightness(&self, _value: i32) {}
+    async fn set_keyboard_brightness(&self, value: i32) {
+        self.set_keyboard_brightness_from(value, BrightnessSource::Client)
+            .await;
+    }
 
     async fn increase_display_brightness(
         &self,
         #[zbus(signal_context)] ctxt: zbus::SignalContext<'_>,
     ) {
-        let value = self.display_brightness().await;
+        let value = self.target_display_brightness().await;
         if let Some(brightness_device) = self.display_brightness_device.as_ref() {
-            let step = brightness_device.brightness_step() as i32;
-            let max = self.max_display_brightness().await;
-            if (max - value) < step {
-                self.set_display_brightness(max).await;
-            } else {
-                self.set_display_brightness((value + step).max(0)).await;
-            }
+            let value = brightness_device.perceptual_step(value.max(0) as u32, true);
+            self.set_display_brightness_from(value as i32, BrightnessSource::Keybinding)
+                .await;
             _ = self.display_brightness_changed(&ctxt).await;
+            _ = self.display_brightness_percent_changed(&ctxt).await;
         }
     }
 
@@ -193,17 +321,79 @@ impl SettingsDaemon {
 
         #[zbus(signal_context)] ctxt: zbus::SignalContext<'_>,
     ) {
-        let value = self.display_brightness().await;
+        let value = self.target_display_brightness().await;
         if let Some(brightness_device) = self.display_brightness_device.as_ref() {
-            let step = brightness_device.brightness_step() as i32;
-            self.set_display_brightness((value - step).max(0)).await;
+            let value = brightness_device.perceptual_step(value.max(0) as u32, false);
+            self.set_display_brightness_from(value as i32, BrightnessSource::Keybinding)
+                .await;
             _ = self.display_brightness_changed(&ctxt).await;
+            _ = self.display_brightness_percent_changed(&ctxt).await;
+        }
+    }
+
+    async fn increase_keyboard_brightness(
+        &self,
+        #[zbus(signal_context)] ctxt: zbus::SignalContext<'_>,
+    ) {
+        let value = self.keyboard_brightness().await;
+        if let Some(brightness_device) = self.keyboard_brightness_device.as_ref() {
+            let step = brightness_device.brightness_step() as i32;
+            let max = self.max_keyboard_brightness().await;
+            self.set_keyboard_brightness_from(
+                (value + step).min(max),
+                BrightnessSource::Keybinding,
+            )
+            .await;
+            _ = self.keyboard_brightness_changed(&ctxt).await;
         }
     }
 
-    async fn increase_keyboard_brightness(&self) {}
+    async fn decrease_keyboard_brightness(
+        &self,
+        #[zbus(signal_context)] ctxt: zbus::SignalContext<'_>,
+    ) {
+        let value = self.keyboard_brightness().await;
+        if let Some(brightness_device) = self.keyboard_brightness_device.as_ref() {
+            let step = brightness_device.brightness_step() as i32;
+            self.set_keyboard_brightness_from((value - step).max(0), BrightnessSource::Keybinding)
+                .await;
+            _ = self.keyboard_brightness_changed(&ctxt).await;
+        }
+    }
+
+    async fn increase_volume(&self, #[zbus(signal_context)] ctxt: zbus::SignalContext<'_>) {
+        let (step, max) = self.volume_step();
+        let result = volume::raise(volume::Device::Sink, step, max).await;
+        Self::announce_volume(&ctxt, volume::Device::Sink, result).await;
+    }
+
+    async fn decrease_volume(&self, #[zbus(signal_context)] ctxt: zbus::SignalContext<'_>) {
+        let (step, _) = self.volume_step();
+        let result = volume::lower(volume::Device::Sink, step).await;
+        Self::announce_volume(&ctxt, volume::Device::Sink, result).await;
+    }
+
+    async fn toggle_mute(&self, #[zbus(signal_context)] ctxt: zbus::SignalContext<'_>) {
+        let result = volume::toggle_mute(volume::Device::Sink).await;
+        Self::announce_volume(&ctxt, volume::Device::Sink, result).await;
+    }
 
-    async fn decrease_keyboard_brightness(&self) {}
+    async fn increase_mic_volume(&self, #[zbus(signal_context)] ctxt: zbus::SignalContext<'_>) {
+        let (step, max) = self.volume_step();
+        let result = volume::raise(volume::Device::Source, step, max).await;
+        Self::announce_volume(&ctxt, volume::Device::Source, result).await;
+    }
+
+    async fn decrease_mic_volume(&self, #[zbus(signal_context)] ctxt: zbus::SignalContext<'_>) {
+        let (step, _) = self.volume_step();
+        let result = volume::lower(volume::Device::Source, step).await;
+        Self::announce_volume(&ctxt, volume::Device::Source, result).await;
+    }
+
+    async fn toggle_mic_mute(&self, #[zbus(signal_context)] ctxt: zbus::SignalContext<'_>) {
+        let result = volume::toggle_mute(volume::Device::Source).await;
+        Self::announce_volume(&ctxt, volume::Device::Source, result).await;
+    }
 
     async fn watch_config(
         &mut self,
@@ -221,9 +411,347 @@ impl SettingsDaemon {
     ) -> zbus::fdo::Result<(ObjectPath<'static>, WellKnownName<'static>)> {
         Self::watch_config_inner(self, Config::new_state(), id, version).await
     }
+
+    /// Whether a battery supports charge thresholds.
+    #[zbus(property)]
+    async fn charge_thresholds_supported(&self) -> bool {
+        !charge_limit::batteries(Path::new(charge_limit::SYSFS_ROOT)).is_empty()
+    }
+
+    /// Limit battery charging to the configured charge thresholds, to preserve battery health.
+    #[zbus(property)]
+    async fn preserve_battery_health(&self) -> bool {
+        self.power_config_tx.borrow().preserve_battery_health
+    }
+
+    #[zbus(property)]
+    async fn set_preserve_battery_health(&self, value: bool) -> zbus::fdo::Result<()> {
+        let Some(helper) = self.power_config_helper.as_ref() else {
+            return Err(zbus::fdo::Error::Failed(
+                "power config is unavailable".to_owned(),
+            ));
+        };
+        helper
+            .set("preserve_battery_health", value)
+            .map_err(|err| zbus::fdo::Error::Failed(format!("{err:?}")))
+    }
+
+    /// Battery percentage below which charging starts, or -1 if unsupported.
+    #[zbus(property)]
+    async fn charge_start_threshold(&self) -> i32 {
+        self.charge_thresholds()
+            .map(|thresholds| i32::from(thresholds.start))
+            .unwrap_or(-1)
+    }
+
+    /// Battery percentage at which charging stops, or -1 if unsupported.
+    #[zbus(property)]
+    async fn charge_end_threshold(&self) -> i32 {
+        self.charge_thresholds()
+            .map(|thresholds| i32::from(thresholds.end))
+            .unwrap_or(-1)
+    }
+
+    /// Emitted when the display or keyboard brightness changes, so that an OSD
+    /// can be shown only for changes the user initiated.
+    ///
+    /// `device` is `display` or `keyboard`, and `source` is one of `keybinding`,
+    /// `client`, `hardware`, or `automatic`. `value` is the level being
+    /// changed to, which a fade may still be approaching.
+    #[zbus(signal)]
+    async fn brightness_changed(
+        ctxt: &SignalContext<'_>,
+        device: &str,
+        value: i32,
+        max: i32,
+        source: &str,
+    ) -> zbus::Result<()>;
+
+    /// Emitted when the volume or mute of the default `sink` or `source` is
+    /// changed through the daemon, with the volume in percent.
+    #[zbus(signal)]
+    async fn volume_changed(
+        ctxt: &SignalContext<'_>,
+        device: &str,
+        volume: u32,
+        muted: bool,
+    ) -> zbus::Result<()>;
 }
 
 impl SettingsDaemon {
+    async fn set_display_brightness_from(&self, value: i32, source: BrightnessSource) {
+        if let Some(brightness_device) = self.display_brightness_device.as_ref() {
+            let value = (value.max(0) as u32).max(self.minimum_brightness(brightness_device));
+            _ = self
+                .auto_brightness_tx
+                .send(auto_brightness::Event::ManualChange);
+            self.apply_display_brightness(value).await;
+            self.save_display_brightness(&brightness_device.state_key(), value);
+            let value = value.min(brightness_device.max_brightness());
+            self.announce_brightness("display", brightness_device, value, source);
+        }
+    }
+
+    async fn set_keyboard_brightness_from(&self, value: i32, source: BrightnessSource) {
+        if let Some(logind_session) = self.logind_session.as_ref() {
+            if let Some(brightness_device) = self.keyboard_brightness_device.as_ref() {
+                let value = value.clamp(0, brightness_device.max_brightness() as i32) as u32;
+                *self.keyboard_brightness_written.lock().unwrap() = Some(value);
+                _ = brightness_device
+                    .set_brightness(logind_session, value)
+                    .await;
+                self.announce_brightness("keyboard", brightness_device, value, source);
+            }
+        }
+    }
+
+    fn announce_brightness(
+        &self,
+        device: &'static str,
+        brightness_device: &BrightnessDevice,
+        value: u32,
+        source: BrightnessSource,
+    ) {
+        _ = self.brightness_change_tx.send(BrightnessChange {
+            device,
+            value,
+            max: brightness_device.max_brightness(),
+            source,
+        });
+    }
+
+    /// Announce a display brightness change made outside of the daemon, if the
+    /// current level differs from the last one it set.
+    async fn display_brightness_hardware_change(&self) {
+        if self.display_fader.target().is_some() {
+            return;
+        }
+        let Some(brightness_device) = self.display_brightness_device.as_ref() else {
+            return;
+        };
+        let Ok(value) = brightness_device.brightness().await else {
+            return;
+        };
+        if self
+            .display_brightness_written
+            .lock()
+            .unwrap()
+            .replace(value)
+            != Some(value)
+        {
+            self.announce_brightness(
+                "display",
+                brightness_device,
+                value,
+                BrightnessSource::Hardware,
+            );
+        }
+    }
+
+    /// Announce a keyboard brightness change made outside of the daemon, such
+    /// as by a firmware hotkey.
+    async fn keyboard_brightness_hardware_change(&self) {
+        let Some(brightness_device) = self.keyboard_brightness_device.as_ref() else {
+            return;
+        };
+        let Ok(value) = brightness_device.brightness().await else {
+            return;
+        };
+        if self
+            .keyboard_brightness_written
+            .lock()
+            .unwrap()
+            .replace(value)
+            != Some(value)
+        {
+            self.announce_brightness(
+                "keyboard",
+                brightness_device,
+                value,
+                BrightnessSource::Hardware,
+            );
+        }
+    }
+
+    async fn apply_display_brightness(&self, value: u32) {
+        if let Some(logind_session) = self.logind_session.as_ref() {
+            if let Some(brightness_device) = self.display_brightness_device.as_ref() {
+                let value = value.min(brightness_device.max_brightness());
+                *self.display_brightness_written.lock().unwrap() = Some(value);
+                let duration = Duration::from_millis(self.brightness_config.fade_duration_ms);
+                if duration.is_zero() || !brightness_device.can_fade() {
+                    self.display_fader.cancel();
+                    _ = brightness_device
+                        .set_brightness(logind_session, value)
+                        .await;
+                } else {
+                    self.display_fader.fade(
+                        logind_session.clone(),
+                        brightness_device.clone(),
+                        value,
+                        duration,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Current display brightness, or the target of the fade in progress.
+    async fn target_display_brightness(&self) -> i32 {
+        match self.display_fader.target() {
+            Some(target) => target as i32,
+            None => self.display_brightness().await,
+        }
+    }
+
+    /// Set the brightness of a display backlight through its own object.
+    ///
+    /// The primary backlight goes through the same path as `DisplayBrightness`.
+    /// Other backlights are still kept above the minimum brightness, and their
+    /// level is saved to restore when they become the primary backlight.
+    pub async fn set_backlight_brightness(
+        &self,
+        device: &BrightnessDevice,
+        value: i32,
+        ctxt: &SignalContext<'_>,
+    ) {
+        if self
+            .display_brightness_device
+            .as_ref()
+            .is_some_and(|primary| primary.sysname() == device.sysname())
+        {
+            self.set_display_brightness_from(value, BrightnessSource::Client)
+                .await;
+            _ = self.display_brightness_changed(ctxt).await;
+            _ = self.display_brightness_percent_changed(ctxt).await;
+            return;
+        }
+
+        if let Some(logind_session) = self.logind_session.as_ref() {
+            let value = (value.max(0) as u32)
+                .max(self.minimum_brightness(device))
+                .min(device.max_brightness());
+            _ = device.set_brightness(logind_session, value).await;
+            self.save_display_brightness(&device.state_key(), value);
+        }
+    }
+
+    /// The configured brightness floor, as a raw level of the given device.
+    fn minimum_brightness(&self, brightness_device: &BrightnessDevice) -> u32 {
+        let minimum = match self.brightness_config.minimum_brightness {
+            brightness::MinimumBrightness::Absolute(value) => value,
+            brightness::MinimumBrightness::Percent(percent) => {
+                brightness_device.percent_to_brightness(percent)
+            }
+        };
+        minimum.min(brightness_device.max_brightness())
+    }
+
+    /// Set the display brightness chosen by automatic brightness, as a perceptual percent.
+    pub async fn set_automatic_display_brightness(&self, percent: f64) {
+        if let Some(brightness_device) = self.display_brightness_device.as_ref() {
+            let value = brightness_device
+                .percent_to_brightness(percent)
+                .max(self.minimum_brightness(brightness_device))
+                .min(brightness_device.max_brightness());
+            self.apply_display_brightness(value).await;
+            self.announce_brightness(
+                "display",
+                brightness_device,
+                value,
+                BrightnessSource::Automatic,
+            );
+        }
+    }
+
+    /// State key of the display brightness levels for the current power source.
+    fn display_brightness_state_key(&self) -> &'static str {
+        if self.brightness_config.power_source_profiles && self.on_battery {
+            DISPLAY_BRIGHTNESS_BATTERY_STATE_KEY
+        } else {
+            DISPLAY_BRIGHTNESS_STATE_KEY
+        }
+    }
+
+    /// Remember the brightness chosen for a backlight, to restore it later.
+    fn save_display_brightness(&self, state_key: &str, value: u32) {
+        let Some(helper) = self.brightness_state_helper.as_ref() else {
+            return;
+        };
+
+        let mut levels = helper
+            .get::<BTreeMap<String, u32>>(self.display_brightness_state_key())
+            .unwrap_or_default();
+        if levels.get(state_key) == Some(&value) {
+            return;
+        }
+        levels.insert(state_key.to_owned(), value);
+
+        if let Err(err) = helper.set(self.display_brightness_state_key(), levels) {
+            eprintln!("Failed to save display brightness: {err:?}");
+        }
+    }
+
+    /// Restore the last brightness chosen for the display backlight, if any.
+    ///
+    /// The saved level is clamped to the minimum brightness, so that a black
+    /// screen is never restored.
+    async fn restore_display_brightness(&self) {
+        let Some(brightness_device) = self.display_brightness_device.as_ref() else {
+            return;
+        };
+        let Some(helper) = self.brightness_state_helper.as_ref() else {
+            return;
+        };
+        let Some(value) = helper
+            .get::<BTreeMap<String, u32>>(self.display_brightness_state_key())
+            .ok()
+            .and_then(|levels| levels.get(&brightness_device.state_key()).copied())
+        else {
+            return;
+        };
+
+        let value = value.max(self.minimum_brightness(brightness_device));
+        self.apply_display_brightness(value).await;
+    }
+
+    fn brightness_config_changed(&mut self, key: &str) {
+        let Some(helper) = self.brightness_config_helper.as_ref() else {
+            return;
+        };
+
+        update_config(helper, &mut self.brightness_config, key, "brightness");
+
+        _ = self.auto_brightness_tx.send(auto_brightness::Event::Config(
+            self.brightness_config.clone(),
+        ));
+    }
+
+    /// Charge thresholds of the first battery supporting them.
+    fn charge_thresholds(&self) -> Option<charge_limit::Thresholds> {
+        let batteries = charge_limit::batteries(Path::new(charge_limit::SYSFS_ROOT));
+        charge_limit::read(batteries.first()?).ok()
+    }
+
+    /// Volume key step and maximum volume, in percent.
+    fn volume_step(&self) -> (u32, u32) {
+        let config = self.sound_config_tx.borrow();
+        (config.volume_step, config.max_volume)
+    }
+
+    async fn announce_volume(
+        ctxt: &SignalContext<'_>,
+        device: volume::Device,
+        result: std::io::Result<volume::Volume>,
+    ) {
+        match result {
+            Ok(volume) => {
+                _ = Self::volume_changed(ctxt, device.as_str(), volume.percent, volume.muted).await;
+            }
+            Err(err) => eprintln!("Failed to change the {} volume: {err}", device.as_str()),
+        }
+    }
+
     async fn watch_config_inner(
         &mut self,
         config: Config,
@@ -253,28 +781,85 @@ impl SettingsDaemon {
     }
 }
 
+/// Load a config entry, falling back to the defaults for keys failing to load.
+fn load_config<T: CosmicConfigEntry + Default>(helper: Option<&cosmic_config::Config>) -> T {
+    let Some(helper) = helper else {
+        return T::default();
+    };
+
+    match T::get_entry(helper) {
+        Ok(config) => config,
+        Err((errs, config)) => {
+            for why in errs {
+                eprintln!("{why}");
+            }
+            config
+        }
+    }
+}
+
+/// Reload a changed key of a config entry.
+fn update_config<T: CosmicConfigEntry>(
+    helper: &cosmic_config::Config,
+    config: &mut T,
+    key: &str,
+    name: &str,
+) {
+    let (errs, _) = config.update_keys(helper, &[key]);
+    for err in errs {
+        eprintln!("Error updating the {name} config {err:?}");
+    }
+}
+
+/// Reload a changed key of a config entry shared with other tasks.
+fn watched_config_changed<T: CosmicConfigEntry>(
+    helper: Option<&cosmic_config::Config>,
+    config_tx: &tokio::sync::watch::Sender<T>,
+    key: &str,
+    name: &str,
+) {
+    if let Some(helper) = helper {
+        config_tx.send_modify(|config| update_config(helper, config, key, name));
+    }
+}
+
 fn backlight_enumerate() -> io::Result<Vec<udev::Device>> {
     let mut enumerator = udev::Enumerator::new()?;
     enumerator.match_subsystem("backlight")?;
     Ok(enumerator.scan_devices()?.collect())
 }
 
-fn backlight_monitor() -> io::Result<AsyncFd<udev::MonitorSocket>> {
+fn kbd_backlight_enumerate() -> io::Result<Vec<udev::Device>> {
+    let mut enumerator = udev::Enumerator::new()?;
+    enumerator.match_subsystem("leds")?;
+    enumerator.match_sysname("*::kbd_backlight")?;
+    Ok(enumerator.scan_devices()?.collect())
+}
+
+fn is_kbd_backlight(device: &udev::Device) -> bool {
+    device
+        .sysname()
+        .to_str()
+        .is_some_and(|sysname| sysname.ends_with("::kbd_backlight"))
+}
+
+fn udev_monitor(subsystem: &str) -> io::Result<AsyncFd<udev::MonitorSocket>> {
     let socket = udev::MonitorBuilder::new()?
-        .match_subsystem("backlight")?
+        .match_subsystem(subsystem)?
         .listen()?;
     AsyncFd::with_interest(socket, Interest::READABLE | Interest::WRITABLE)
 }
 
 // Choose backlight with most "precision". This is what `light` does.
 async fn choose_best_backlight(
+    subsystem: &'static str,
     udev_devices: &HashMap<PathBuf, udev::Device>,
 ) -> Option<BrightnessDevice> {
     let mut best_backlight = None;
     let mut best_max_brightness = 0;
     for device in udev_devices.values() {
         if let Some(sysname) = device.sysname().to_str() {
-            match BrightnessDevice::new("backlight", sysname.to_owned()).await {
+            match BrightnessDevice::new(subsystem, sysname.to_owned()).await {
                 Ok(brightness_device) => {
                     if brightness_device.max_brightness() > best_max_brightness {
                         best_max_brightness = brightness_device.max_brightness();
@@ -300,39 +885,84 @@ async fn backlight_monitor_task(
 
     let ctxt = zbus::SignalContext::new(&connection, DBUS_PATH).unwrap();
 
-    match backlight_monitor() {
+    for device in backlights.values() {
+        let Some(sysname) = device.sysname().to_str() else {
+            continue;
+        };
+        if let Some(path) = backlight::add_sysfs(&connection, sysname).await {
+            interface
+                .get_mut()
+                .await
+                .backlights
+                .insert(sysname.to_owned(), path);
+        }
+    }
+    {
+        let interface = interface.get().await;
+        interface.restore_display_brightness().await;
+        _ = interface.display_brightness_changed(&ctxt).await;
+        _ = interface.display_brightness_percent_changed(&ctxt).await;
+        _ = interface.primary_backlight_changed(&ctxt).await;
+    }
+
+    match udev_monitor("backlight") {
         Ok(mut socket) => {
             loop {
                 let mut socket = socket.writable_mut().await.unwrap(); // XXX
                 for evt in socket.get_inner().iter() {
                     eprintln!("{:?}: {:?}", evt.event_type(), evt.device());
+                    let sysname = evt.sysname().to_string_lossy().into_owned();
                     match evt.event_type() {
                         udev::EventType::Add => {
                             backlights.insert(evt.syspath().to_owned(), evt.device());
-                            let device = choose_best_backlight(&backlights).await;
-                            interface.get_mut().await.display_brightness_device = device;
-                            _ = interface
-                                .get()
-                                .await
-                                .display_brightness_changed(&ctxt)
-                                .await;
+                            let device = choose_best_backlight("backlight", &backlights).await;
+                            let path = backlight::add_sysfs(&connection, &sysname).await;
+                            let reappeared = device
+                                .as_ref()
+                                .is_some_and(|device| device.sysname() == sysname);
+                            {
+                                let mut interface = interface.get_mut().await;
+                                interface.display_brightness_device =
+                                    device.or_else(|| interface.ddc_monitors.first().cloned());
+                                if let Some(path) = path {
+                                    interface.backlights.insert(sysname, path);
+                                }
+                            }
+                            let interface = interface.get().await;
+                            if reappeared {
+                                interface.restore_display_brightness().await;
+                            }
+                            _ = interface.display_brightness_changed(&ctxt).await;
+                            _ = interface.display_brightness_percent_changed(&ctxt).await;
+                            _ = interface.primary_backlight_changed(&ctxt).await;
                         }
                         udev::EventType::Remove => {
                             backlights.remove(evt.syspath());
-                            let device = choose_best_backlight(&backlights).await;
-                            interface.get_mut().await.display_brightness_device = device;
-                            _ = interface
-                                .get()
-                                .await
-                                .display_brightness_changed(&ctxt)
-                                .await;
+                            let device = choose_best_backlight("backlight", &backlights).await;
+                            backlight::remove(&connection, &sysname).await;
+                            {
+                                let mut interface = interface.get_mut().await;
+                                interface.display_brightness_device =
+                                    device.or_else(|| interface.ddc_monitors.first().cloned());
+                                interface.backlights.remove(&sysname);
+                            }
+                            let interface = interface.get().await;
+                            _ = interface.display_brightness_changed(&ctxt).await;
+                            _ = interface.display_brightness_percent_changed(&ctxt).await;
+                            _ = interface.primary_backlight_changed(&ctxt).await;
                         }
                         udev::EventType::Change => {
-                            _ = interface
-                                .get()
-                                .await
-                                .display_brightness_changed(&ctxt)
-                                .await;
+                            backlight::brightness_changed(&connection, &sysname).await;
+                            let interface = interface.get().await;
+                            if interface
+                                .display_brightness_device
+                                .as_ref()
+                                .is_some_and(|device| device.sysname() == sysname)
+                            {
+                                interface.display_brightness_hardware_change().await;
+                            }
+                            _ = interface.display_brightness_changed(&ctxt).await;
+                            _ = interface.display_brightness_percent_changed(&ctxt).await;
                         }
                         _ => {}
                     }
@@ -344,6 +974,233 @@ async fn backlight_monitor_task(
     };
 }
 
+/// I2C buses that may reach a connected external display, by sysname.
+fn ddc_enumerate() -> io::Result<BTreeMap<String, udev::Device>> {
+    let mut enumerator = udev::Enumerator::new()?;
+    enumerator.match_subsystem("i2c-dev")?;
+    Ok(enumerator
+        .scan_devices()?
+        .filter(|bus| ddc::is_display_bus(bus) && ddc::is_connected(bus))
+        .filter_map(|bus| Some((bus.sysname().to_str()?.to_owned(), bus)))
+        .collect())
+}
+
+/// Probe I2C buses for monitors supporting DDC/CI brightness control.
+async fn ddc_probe(buses: Vec<&udev::Device>) -> Vec<BrightnessDevice> {
+    let buses: Vec<_> = buses
+        .into_iter()
+        .filter_map(|bus| {
+            let sysname = bus.sysname().to_str()?.to_owned();
+            Some((sysname, ddc::connector(bus), ddc::edid(bus)))
+        })
+        .collect();
+
+    let mut monitors = Vec::new();
+    for (sysname, connector, edid) in buses {
+        if let Ok(device) = BrightnessDevice::new_ddc(&sysname, connector, edid).await {
+            monitors.push(device);
+        }
+    }
+    monitors
+}
+
+/// Probe the buses of newly connected displays and drop the monitors that
+/// were disconnected, tracking the buses seen so far in `buses`.
+///
+/// A bus is only probed once while its display stays connected, since
+/// probing is slow and every connector reports a change on hotplug.
+async fn update_ddc_monitors(
+    connection: &zbus::Connection,
+    interface: &zbus::InterfaceRef<SettingsDaemon>,
+    ctxt: &SignalContext<'_>,
+    buses: &mut BTreeSet<String>,
+) {
+    let current = match ddc_enumerate() {
+        Ok(current) => current,
+        Err(err) => {
+            eprintln!("Failed to enumerate I2C buses: {}", err);
+            return;
+        }
+    };
+
+    let removed: Vec<String> = buses
+        .iter()
+        .filter(|sysname| !current.contains_key(*sysname))
+        .cloned()
+        .collect();
+    let added: Vec<&udev::Device> = current
+        .iter()
+        .filter(|(sysname, _)| !buses.contains(*sysname))
+        .map(|(_, bus)| bus)
+        .collect();
+    if removed.is_empty() && added.is_empty() {
+        return;
+    }
+    let added = ddc_probe(added).await;
+    *buses = current.into_keys().collect();
+
+    for sysname in &removed {
+        backlight::remove(connection, sysname).await;
+    }
+    let mut paths = Vec::new();
+    for device in &added {
+        if let Some(path) = backlight::add(connection, device.clone()).await {
+            paths.push((device.sysname().to_owned(), path));
+        }
+    }
+
+    // A backlight is always preferred over an external monitor.
+    let primary_changed = {
+        let mut interface = interface.get_mut().await;
+        interface
+            .ddc_monitors
+            .retain(|monitor| !removed.iter().any(|sysname| sysname == monitor.sysname()));
+        interface.ddc_monitors.extend(added);
+        for sysname in &removed {
+            interface.backlights.remove(sysname);
+        }
+        interface.backlights.extend(paths);
+
+        if matches!(
+            interface.display_brightness_device,
+            Some(BrightnessDevice::Sysfs(_))
+        ) {
+            false
+        } else {
+            let primary = interface.ddc_monitors.first().cloned();
+            let changed = interface
+                .display_brightness_device
+                .as_ref()
+                .map(|device| device.sysname())
+                != primary.as_ref().map(|device| device.sysname());
+            interface.display_brightness_device = primary;
+            changed
+        }
+    };
+
+    if primary_changed {
+        let interface = interface.get().await;
+        interface.restore_display_brightness().await;
+        _ = interface.display_brightness_changed(ctxt).await;
+        _ = interface.display_brightness_percent_changed(ctxt).await;
+        _ = interface.primary_backlight_changed(ctxt).await;
+    }
+}
+
+async fn ddc_monitor_task(connection: zbus::Connection) {
+    let interface = connection
+        .object_server()
+        .interface::<_, SettingsDaemon>(DBUS_PATH)
+        .await
+        .unwrap();
+
+    let ctxt = zbus::SignalContext::new(&connection, DBUS_PATH).unwrap();
+
+    let mut buses = BTreeSet::new();
+    update_ddc_monitors(&connection, &interface, &ctxt, &mut buses).await;
+
+    // Monitors are hotplugged through their DRM connector rather than the I2C bus,
+    // which exists as long as the graphics card does.
+    match udev_monitor("drm") {
+        Ok(mut socket) => loop {
+            let mut socket = socket.writable_mut().await.unwrap(); // XXX
+            let hotplug = socket
+                .get_inner()
+                .iter()
+                .any(|evt| evt.event_type() == udev::EventType::Change);
+            socket.clear_ready();
+            if hotplug {
+                update_ddc_monitors(&connection, &interface, &ctxt, &mut buses).await;
+            }
+        },
+        Err(err) => eprintln!("Error creating udev drm monitor: {}", err),
+    };
+}
+
+async fn kbd_backlight_monitor_task(
+    mut kbd_backlights: HashMap<PathBuf, udev::Device>,
+    connection: zbus::Connection,
+) {
+    let interface = connection
+        .object_server()
+        .interface::<_, SettingsDaemon>(DBUS_PATH)
+        .await
+        .unwrap();
+
+    let ctxt = zbus::SignalContext::new(&connection, DBUS_PATH).unwrap();
+
+    match udev_monitor("leds") {
+        Ok(mut socket) => {
+            loop {
+                let mut socket = socket.writable_mut().await.unwrap(); // XXX
+                for evt in socket
+                    .get_inner()
+                    .iter()
+                    .filter(|evt| is_kbd_backlight(evt))
+                {
+                    match evt.event_type() {
+                        udev::EventType::Add => {
+                            kbd_backlights.insert(evt.syspath().to_owned(), evt.device());
+                            let device = choose_best_backlight("leds", &kbd_backlights).await;
+                            interface.get_mut().await.keyboard_brightness_device = device;
+                            let interface = interface.get().await;
+                            _ = interface.keyboard_brightness_changed(&ctxt).await;
+                            _ = interface.max_keyboard_brightness_changed(&ctxt).await;
+                        }
+                        udev::EventType::Remove => {
+                            kbd_backlights.remove(evt.syspath());
+                            let device = choose_best_backlight("leds", &kbd_backlights).await;
+                            interface.get_mut().await.keyboard_brightness_device = device;
+                            let interface = interface.get().await;
+                            _ = interface.keyboard_brightness_changed(&ctxt).await;
+                            _ = interface.max_keyboard_brightness_changed(&ctxt).await;
+                        }
+                        udev::EventType::Change => {
+                            let interface = interface.get().await;
+                            interface.keyboard_brightness_hardware_change().await;
+                            _ = interface.keyboard_brightness_changed(&ctxt).await;
+                        }
+                        _ => {}
+                    }
+                }
+                socket.clear_ready();
+            }
+        }
+        Err(err) => eprintln!("Error creating udev leds monitor: {}", err),
+    };
+}
+
+/// Switch between the AC and battery display brightness when the power source changes.
+async fn power_source_task(
+    mut ac_plugged: tokio::sync::watch::Receiver<bool>,
+    connection: zbus::Connection,
+) {
+    let interface = connection
+        .object_server()
+        .interface::<_, SettingsDaemon>(DBUS_PATH)
+        .await
+        .unwrap();
+
+    let ctxt = zbus::SignalContext::new(&connection, DBUS_PATH).unwrap();
+
+    while let Ok(()) = ac_plugged.changed().await {
+        let on_battery = !*ac_plugged.borrow_and_update();
+        let switched = {
+            let mut interface = interface.get_mut().await;
+            let switched = interface.on_battery != on_battery;
+            interface.on_battery = on_battery;
+            switched && interface.brightness_config.power_source_profiles
+        };
+
+        if switched {
+            let interface = interface.get().await;
+            interface.restore_display_brightness().await;
+            _ = interface.display_brightness_changed(&ctxt).await;
+            _ = interface.display_brightness_percent_changed(&ctxt).await;
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum Change {
     Config(String, String, u64),
@@ -353,6 +1210,13 @@ pub enum Change {
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> zbus::Result<()> {
+    let mut args = std::env::args().skip(1);
+    if args.next().as_deref() == Some(charge_limit::HELPER_COMMAND) {
+        std::process::exit(charge_limit::helper(args));
+    }
+
+    localize::localize();
+
     let (theme_cleanup_done_tx, mut theme_cleanup_done_rx) = tokio::sync::mpsc::channel(1);
     let (sigterm_tx, sigterm_rx) = tokio::sync::broadcast::channel(1);
 
@@ -382,7 +1246,20 @@ async fn main() -> zbus::Result<()> {
                 .into_iter()
                 .map(|i| (i.syspath().to_owned(), i))
                 .collect();
-            let display_brightness_device = choose_best_backlight(&backlights).await;
+            let display_brightness_device = choose_best_backlight("backlight", &backlights).await;
+
+            let kbd_backlights = match kbd_backlight_enumerate() {
+                Ok(kbd_backlights) => kbd_backlights,
+                Err(err) => {
+                    eprintln!("Failed to enumerate keyboard backlights: {}", err);
+                    Vec::new()
+                }
+            };
+            let kbd_backlights: HashMap<_, _> = kbd_backlights
+                .into_iter()
+                .map(|i| (i.syspath().to_owned(), i))
+                .collect();
+            let keyboard_brightness_device = choose_best_backlight("leds", &kbd_backlights).await;
 
             let logind_session = async {
                 let connection = zbus::Connection::system().await?;
@@ -473,9 +1350,65 @@ async fn main() -> zbus::Result<()> {
             }
             let watched_configs = Arc::new(RwLock::new(HashMap::new()));
             let watched_states = Arc::new(RwLock::new(HashMap::new()));
+            let brightness_config_helper = brightness::Config::context()
+                .map_err(|err| eprintln!("Failed to open brightness config: {err:?}"))
+                .ok();
+            let brightness_config: brightness::Config =
+                load_config(brightness_config_helper.as_ref());
+
+            let power_config_helper = power::Config::context()
+                .map_err(|err| eprintln!("Failed to open power config: {err:?}"))
+                .ok();
+            let power_config: power::Config = load_config(power_config_helper.as_ref());
+            // Applying the charge thresholds asks for authentication, which is
+            // not to happen at every login when the firmware resets them. They
+            // are applied again when the power config changes.
+            if power_config.preserve_battery_health && !charge_limit::is_applied(&power_config) {
+                eprintln!("Charge thresholds differ from the power config, which applies them when changed");
+            }
+            let (power_config_tx, power_config_rx) = tokio::sync::watch::channel(power_config);
+
+            let sound_config_helper = sound::Config::context()
+                .map_err(|err| eprintln!("Failed to open sound config: {err:?}"))
+                .ok();
+            let sound_config: sound::Config = load_config(sound_config_helper.as_ref());
+            let (sound_config_tx, sound_config_rx) = tokio::sync::watch::channel(sound_config);
+            let backend = playback::Backend::detect();
+            if matches!(backend, playback::Backend::Null(_)) {
+                eprintln!("Found no audio player for event sounds");
+            }
+            let event_sounds =
+                event_sounds::EventSounds::new(sound_config_rx, playback::Player::new(backend));
+
+            let (auto_brightness_tx, auto_brightness_rx) = tokio::sync::mpsc::unbounded_channel();
+            let (brightness_change_tx, mut brightness_change_rx) =
+                tokio::sync::mpsc::unbounded_channel::<BrightnessChange>();
+            let auto_brightness_config = brightness_config.clone();
+
             let settings_daemon = SettingsDaemon {
                 logind_session: logind_session.ok(),
                 display_brightness_device,
+                keyboard_brightness_device,
+                ddc_monitors: Vec::new(),
+                backlights: BTreeMap::new(),
+                brightness_config,
+                brightness_config_helper,
+                brightness_state_helper: cosmic_config::Config::new_state(
+                    brightness::ID,
+                    brightness::Config::VERSION,
+                )
+                .map_err(|err| eprintln!("Failed to open brightness state: {err:?}"))
+                .ok(),
+                display_fader: fade::Fader::default(),
+                on_battery: false,
+                display_brightness_written: std::sync::Mutex::new(None),
+                keyboard_brightness_written: std::sync::Mutex::new(None),
+                brightness_change_tx,
+                auto_brightness_tx,
+                power_config_helper,
+                power_config_tx,
+                sound_config_helper,
+                sound_config_tx,
                 watched_configs: watched_configs.clone(),
                 watched_states: watched_states.clone(),
             };
@@ -483,6 +1416,8 @@ async fn main() -> zbus::Result<()> {
             let connection = zbus::ConnectionBuilder::session()?
                 .name(DBUS_NAME)?
                 .serve_at(DBUS_PATH, settings_daemon)?
+                .serve_at(DBUS_PATH, power_status::Power::default())?
+                .serve_at(DBUS_PATH, sound_service::Sound::new(event_sounds.clone()))?
                 .build()
                 .await?;
 
@@ -491,7 +1426,76 @@ async fn main() -> zbus::Result<()> {
                 backlight_monitor_task(backlights, conn_clone).await;
             });
 
-            tokio::task::spawn_local(battery::monitor());
+            let conn_clone = connection.clone();
+            task::spawn_local(async move {
+                ddc_monitor_task(conn_clone).await;
+            });
+
+            let conn_clone = connection.clone();
+            task::spawn_local(async move {
+                let ctxt = SignalContext::new(&conn_clone, DBUS_PATH).unwrap();
+                while let Some(change) = brightness_change_rx.recv().await {
+                    _ = SettingsDaemon::brightness_changed(
+                        &ctxt,
+                        change.device,
+                        change.value as i32,
+                        change.max as i32,
+                        change.source.as_str(),
+                    )
+                    .await;
+                }
+            });
+
+            let conn_clone = connection.clone();
+            task::spawn_local(async move {
+                kbd_backlight_monitor_task(kbd_backlights, conn_clone).await;
+            });
+
+            let conn_clone = connection.clone();
+            task::spawn_local(async move {
+                match zbus::Connection::system().await {
+                    Ok(system_conn) => {
+                        auto_brightness::run(
+                            conn_clone,
+                            system_conn,
+                            auto_brightness_rx,
+                            auto_brightness_config,
+                        )
+                        .await
+                    }
+                    Err(err) => eprintln!("Failed to start automatic brightness: {err}"),
+                }
+            });
+
+            let (ac_plugged_tx, ac_plugged_rx) = tokio::sync::watch::channel(true);
+            let conn_clone = connection.clone();
+            task::spawn_local(async move {
+                let system_conn = match zbus::Connection::system().await {
+                    Ok(system_conn) => system_conn,
+                    Err(err) => {
+                        eprintln!("Failed to start battery monitoring: {err}");
+                        return;
+                    }
+                };
+                task::spawn_local(battery::peripheral_monitor(
+                    system_conn.clone(),
+                    power_config_rx.clone(),
+                ));
+                task::spawn_local(battery_history::record(system_conn.clone()));
+                battery::monitor(
+                    conn_clone,
+                    system_conn,
+                    ac_plugged_tx,
+                    power_config_rx,
+                    event_sounds,
+                )
+                .await;
+            });
+
+            let conn_clone = connection.clone();
+            task::spawn_local(async move {
+                power_source_task(ac_plugged_rx, conn_clone).await;
+            });
 
             let conn_clone = connection.clone();
             let (ready_oneshot_tx, mut ready_oneshot_rx) = tokio::sync::oneshot::channel();
@@ -543,15 +1547,15 @@ async fn main() -> zbus::Result<()> {
 
             let conn_clone = connection.clone();
             task::spawn_local(async move {
+                let mut charge_limit_apply: Option<task::JoinHandle<()>> = None;
                 while let Some(changes) = rx.recv().await {
-                    let Ok(settings_daemon) = conn_clone
+                    let Ok(interface) = conn_clone
                         .object_server()
                         .interface::<_, SettingsDaemon>(DBUS_PATH)
                         .await
                     else {
                         continue;
                     };
-                    let settings_daemon = settings_daemon.get().await;
                     for c in changes {
                         if let Change::Config(id, key, version) = c {
                             if id.as_str() == cosmic_theme::THEME_MODE_ID {
@@ -582,7 +1586,53 @@ async fn main() -> zbus::Result<()> {
                                 if let Err(err) = xkb_tx.send(()).await {
                                     eprintln!("Failed to send xkb layout update: {err:?}");
                                 }
+                            } else if id.as_str() == brightness::ID {
+                                interface.get_mut().await.brightness_config_changed(&key);
+                            } else if id.as_str() == power::ID {
+                                let config = {
+                                    let settings_daemon = interface.get().await;
+                                    watched_config_changed(
+                                        settings_daemon.power_config_helper.as_ref(),
+                                        &settings_daemon.power_config_tx,
+                                        &key,
+                                        "power",
+                                    );
+                                    settings_daemon.power_config_tx.borrow().clone()
+                                };
+                                if charge_limit::CONFIG_KEYS.contains(&key.as_str()) {
+                                    // Applying waits for authentication, which a
+                                    // newer change supersedes.
+                                    if let Some(previous) = charge_limit_apply.take() {
+                                        previous.abort();
+                                    }
+                                    let interface = interface.clone();
+                                    charge_limit_apply = Some(task::spawn_local(async move {
+                                        if let Err(err) = charge_limit::apply(&config).await {
+                                            eprintln!("Failed to apply charge thresholds: {err}");
+                                        }
+                                        let settings_daemon = interface.get().await;
+                                        let ctxt = interface.signal_context();
+                                        _ = settings_daemon
+                                            .preserve_battery_health_changed(ctxt)
+                                            .await;
+                                        _ = settings_daemon
+                                            .charge_start_threshold_changed(ctxt)
+                                            .await;
+                                        _ = settings_daemon
+                                            .charge_end_threshold_changed(ctxt)
+                                            .await;
+                                    }));
+                                }
+                            } else if id.as_str() == sound::ID {
+                                let settings_daemon = interface.get().await;
+                                watched_config_changed(
+                                    settings_daemon.sound_config_helper.as_ref(),
+                                    &settings_daemon.sound_config_tx,
+                                    &key,
+                                    "sound",
+                                );
                             }
+                            let settings_daemon = interface.get().await;
                             let read_guard = settings_daemon.watched_configs.read().await;
                             let Some((conn, path, _)) = read_guard.get(&(id.to_string(), version))
                             else {
@@ -604,6 +1654,7 @@ async fn main() -> zbus::Result<()> {
                                 eprintln!("Failed to send config changed signal: {}", err);
                             }
                         } else if let Change::State(id, key, version) = c {
+                            let settings_daemon = interface.get().await;
                             let read_guard = settings_daemon.watched_states.read().await;
                             let Some((conn, path, _)) = read_guard.get(&(id.to_string(), version))
                             else {
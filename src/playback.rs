@@ -0,0 +1,258 @@
+// Copyright 2025 System76 <info@system76.com>
+// SPDX-License-Identifier: MPL-2.0
+
+//! Playback of sound files through whichever audio player is installed.
+
+use std::collections::HashMap;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+
+use tokio::process::{Child, Command};
+use tokio::sync::oneshot;
+
+/// Sounds allowed to play at once. Further sounds are dropped rather than
+/// queued, since event sounds are only meaningful when they happen.
+const MAX_CONCURRENT_SOUNDS: usize = 3;
+
+/// How sound files are played.
+#[derive(Clone, Debug)]
+pub enum Backend {
+    /// PipeWire's `pw-play`.
+    PwPlay,
+    /// PulseAudio's `paplay`, also provided by `pipewire-pulse` setups.
+    PaPlay,
+    /// ALSA's `aplay`, which only plays WAV files and has no volume control.
+    /// Other files are skipped, since it would play them as noise.
+    Aplay,
+    /// Plays nothing, recording the requested files if given a log.
+    Null(Option<Arc<Mutex<Vec<PathBuf>>>>),
+}
+
+impl Backend {
+    /// The first audio player found in `PATH`, or the null backend if none is.
+    pub fn detect() -> Self {
+        [
+            ("pw-play", Self::PwPlay),
+            ("paplay", Self::PaPlay),
+            ("aplay", Self::Aplay),
+        ]
+        .into_iter()
+        .find(|(program, _)| in_path(program))
+        .map_or(Self::Null(None), |(_, backend)| backend)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::PwPlay => "pw-play",
+            Self::PaPlay => "paplay",
+            Self::Aplay => "aplay",
+            Self::Null(_) => "null",
+        }
+    }
+
+    /// Start playing a file at a volume from 0.0 to 1.0, setting `properties`
+    /// on the playback stream where supported. Returns the player process,
+    /// if one was started.
+    fn spawn(
+        &self,
+        path: &Path,
+        volume: f32,
+        properties: &HashMap<String, String>,
+    ) -> io::Result<Option<Child>> {
+        let volume = volume.clamp(0.0, 1.0);
+        let mut command = match self {
+            Self::PwPlay => {
+                let mut command = Command::new("pw-play");
+                command
+                    .arg("--volume")
+                    .arg(volume.to_string())
+                    .arg("--properties")
+                    .arg(properties_json(properties));
+                command
+            }
+            Self::PaPlay => {
+                let mut command = Command::new("paplay");
+                // PulseAudio volumes are linear, with 65536 as 100%.
+                command.arg(format!("--volume={}", (volume * 65536.0).round() as u32));
+                for (key, value) in properties {
+                    command.arg(format!("--property={key}={value}"));
+                }
+                command
+            }
+            Self::Aplay => {
+                let is_wav = path
+                    .extension()
+                    .is_some_and(|extension| extension.eq_ignore_ascii_case("wav"));
+                if !is_wav {
+                    return Ok(None);
+                }
+                let mut command = Command::new("aplay");
+                command.arg("--quiet");
+                command
+            }
+            Self::Null(log) => {
+                if let Some(log) = log {
+                    log.lock().unwrap().push(path.to_owned());
+                }
+                return Ok(None);
+            }
+        };
+
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::inherit())
+            .arg(path)
+            .spawn()
+            .map(Some)
+    }
+}
+
+/// Whether an executable with the given name is in `PATH`.
+pub(crate) fn in_path(program: &str) -> bool {
+    let Some(path) = std::env::var_os("PATH") else {
+        return false;
+    };
+
+    std::env::split_paths(&path).any(|dir| {
+        std::fs::metadata(dir.join(program))
+            .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+    })
+}
+
+/// Format stream properties as a JSON object.
+fn properties_json(properties: &HashMap<String, String>) -> String {
+    let escape = |value: &str| value.replace('\\', "\\\\").replace('"', "\\\"");
+    let entries = properties
+        .iter()
+        .map(|(key, value)| format!("\"{}\": \"{}\"", escape(key), escape(value)))
+        .collect::<Vec<_>>();
+    format!("{{ {} }}", entries.join(", "))
+}
+
+/// A sound started by a [`Player`]. Dropping it lets the sound finish.
+pub struct Playback {
+    cancel: oneshot::Sender<()>,
+}
+
+impl Playback {
+    /// Stop the sound if it is still playing.
+    pub fn cancel(self) {
+        _ = self.cancel.send(());
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.cancel.is_closed()
+    }
+}
+
+/// Plays sound files through a backend, reaping the player processes and
+/// limiting how many sounds play at once.
+#[derive(Clone)]
+pub struct Player {
+    backend: Arc<Backend>,
+    /// Files currently playing.
+    playing: Arc<Mutex<Vec<PathBuf>>>,
+}
+
+impl Player {
+    pub fn new(backend: Backend) -> Self {
+        Self {
+            backend: Arc::new(backend),
+            playing: Arc::default(),
+        }
+    }
+
+    /// Play a file at a volume from 0.0 to 1.0.
+    ///
+    /// Nothing is played if the same file is already playing, so that
+    /// repeated alerts do not overlap, if too many sounds are playing, or if
+    /// the backend cannot play the file.
+    pub fn play(
+        &self,
+        path: &Path,
+        volume: f32,
+        properties: &HashMap<String, String>,
+    ) -> Option<Playback> {
+        {
+            let mut playing = self.playing.lock().unwrap();
+            if playing.len() >= MAX_CONCURRENT_SOUNDS || playing.iter().any(|p| p == path) {
+                return None;
+            }
+            playing.push(path.to_owned());
+        }
+
+        let finished = {
+            let playing = self.playing.clone();
+            let path = path.to_owned();
+            move || {
+                let mut playing = playing.lock().unwrap();
+                if let Some(pos) = playing.iter().position(|p| *p == path) {
+                    playing.remove(pos);
+                }
+            }
+        };
+
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        match self.backend.spawn(path, volume, properties) {
+            Ok(Some(mut child)) => {
+                tokio::spawn(async move {
+                    tokio::select! {
+                        _ = child.wait() => (),
+                        Ok(()) = cancel_rx => {
+                            _ = child.kill().await;
+                        }
+                    }
+                    finished();
+                });
+            }
+            Ok(None) => {
+                finished();
+                // The null backend stands in for playing sounds in tests,
+                // while the others skip sounds they cannot play.
+                if !matches!(*self.backend, Backend::Null(_)) {
+                    return None;
+                }
+            }
+            Err(err) => {
+                finished();
+                eprintln!(
+                    "Failed to play {} with {}: {err}",
+                    path.display(),
+                    self.backend.name()
+                );
+                return None;
+            }
+        }
+
+        Some(Playback { cancel: cancel_tx })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aplay_skips_files_other_than_wav() {
+        let properties = HashMap::new();
+        for path in ["/missing/bell.oga", "/missing/bell.ogg", "/missing/bell"] {
+            assert!(matches!(
+                Backend::Aplay.spawn(Path::new(path), 1.0, &properties),
+                Ok(None)
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn skipped_sounds_are_not_played() {
+        let player = Player::new(Backend::Aplay);
+        assert!(player
+            .play(Path::new("/missing/bell.oga"), 1.0, &HashMap::new())
+            .is_none());
+        assert!(player.playing.lock().unwrap().is_empty());
+    }
+}
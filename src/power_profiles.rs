@@ -0,0 +1,177 @@
+// Copyright 2025 System76 <info@system76.com>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use cosmic_settings_config::power;
+
+pub const POWER_SAVER: &str = "power-saver";
+
+#[zbus::proxy(
+    default_service = "net.hadess.PowerProfiles",
+    interface = "net.hadess.PowerProfiles",
+    default_path = "/net/hadess/PowerProfiles"
+)]
+trait PowerProfiles {
+    #[zbus(property)]
+    fn active_profile(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn set_active_profile(&self, profile: &str) -> zbus::Result<()>;
+}
+
+/// Connect to `power-profiles-daemon`, preferring its current bus name over
+/// the legacy `net.hadess.PowerProfiles` one.
+pub async fn connect(connection: &zbus::Connection) -> zbus::Result<PowerProfilesProxy<'static>> {
+    let upower = async {
+        let proxy = PowerProfilesProxy::builder(connection)
+            .destination("org.freedesktop.UPower.PowerProfiles")?
+            .path("/org/freedesktop/UPower/PowerProfiles")?
+            .interface("org.freedesktop.UPower.PowerProfiles")?
+            .build()
+            .await?;
+        proxy.active_profile().await?;
+        Ok::<_, zbus::Error>(proxy)
+    };
+
+    match upower.await {
+        Ok(proxy) => Ok(proxy),
+        Err(_) => {
+            let proxy = PowerProfilesProxy::new(connection).await?;
+            proxy.active_profile().await?;
+            Ok(proxy)
+        }
+    }
+}
+
+/// Switches power profiles as the battery runs low and the AC plug state changes.
+pub struct ProfileSwitcher {
+    proxy: PowerProfilesProxy<'static>,
+    /// Profile active before switching to power saver for a low battery.
+    restore: Option<String>,
+}
+
+impl ProfileSwitcher {
+    pub fn new(proxy: PowerProfilesProxy<'static>) -> Self {
+        Self {
+            proxy,
+            restore: None,
+        }
+    }
+
+    async fn set(&self, profile: &str) {
+        if let Err(err) = self.proxy.set_active_profile(profile).await {
+            eprintln!("Failed to set power profile to {profile}: {err}");
+        }
+    }
+
+    /// Switch to power saver, remembering the profile to restore on AC.
+    pub async fn on_low_battery(&mut self) {
+        if self.restore.is_some() {
+            return;
+        }
+
+        match self.proxy.active_profile().await {
+            Ok(profile) if profile == POWER_SAVER => (),
+            Ok(profile) => {
+                self.restore = Some(profile);
+                self.set(POWER_SAVER).await;
+            }
+            Err(err) => eprintln!("Failed to get the active power profile: {err}"),
+        }
+    }
+
+    /// Switch to the configured profile for the new power source. Without one,
+    /// plugging in restores the profile active before the battery ran low.
+    pub async fn on_ac_plug(&mut self, ac_plugged: bool, config: &power::Config) {
+        if ac_plugged {
+            let restore = self.restore.take();
+            if let Some(profile) = config.ac_power_profile.as_deref().or(restore.as_deref()) {
+                self.set(profile).await;
+            }
+        } else if let Some(profile) = config.battery_power_profile.as_deref() {
+            self.set(profile).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    const POWER_PROFILES_PATH: &str = "/net/hadess/PowerProfiles";
+
+    struct MockPowerProfiles {
+        active_profile: String,
+    }
+
+    #[zbus::interface(name = "net.hadess.PowerProfiles")]
+    impl MockPowerProfiles {
+        #[zbus(property)]
+        fn active_profile(&self) -> String {
+            self.active_profile.clone()
+        }
+
+        #[zbus(property)]
+        fn set_active_profile(&mut self, profile: String) {
+            self.active_profile = profile;
+        }
+    }
+
+    async fn switcher(active_profile: &str) -> (zbus::Connection, ProfileSwitcher) {
+        let (server, client) = test_util::p2p(
+            POWER_PROFILES_PATH,
+            MockPowerProfiles {
+                active_profile: active_profile.to_owned(),
+            },
+        )
+        .await;
+
+        let proxy = PowerProfilesProxy::builder(&client)
+            .cache_properties(zbus::proxy::CacheProperties::No)
+            .build()
+            .await
+            .unwrap();
+        (server, ProfileSwitcher::new(proxy))
+    }
+
+    async fn active_profile(server: &zbus::Connection) -> String {
+        server
+            .object_server()
+            .interface::<_, MockPowerProfiles>(POWER_PROFILES_PATH)
+            .await
+            .unwrap()
+            .get()
+            .await
+            .active_profile
+            .clone()
+    }
+
+    #[tokio::test]
+    async fn power_saver_on_low_battery() {
+        let (server, mut switcher) = switcher("performance").await;
+        let config = power::Config::default();
+
+        switcher.on_low_battery().await;
+        assert_eq!(active_profile(&server).await, POWER_SAVER);
+
+        switcher.on_low_battery().await;
+        switcher.on_ac_plug(true, &config).await;
+        assert_eq!(active_profile(&server).await, "performance");
+    }
+
+    #[tokio::test]
+    async fn default_profiles_per_power_source() {
+        let (server, mut switcher) = switcher("balanced").await;
+        let config = power::Config {
+            ac_power_profile: Some("performance".to_owned()),
+            battery_power_profile: Some(POWER_SAVER.to_owned()),
+            ..Default::default()
+        };
+
+        switcher.on_ac_plug(false, &config).await;
+        assert_eq!(active_profile(&server).await, POWER_SAVER);
+
+        switcher.on_ac_plug(true, &config).await;
+        assert_eq!(active_profile(&server).await, "performance");
+    }
+}
@@ -0,0 +1,114 @@
+// Copyright 2025 System76 <info@system76.com>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use upower_dbus::BatteryLevel;
+use zbus::SignalContext;
+
+use crate::{battery_history, DBUS_PATH};
+
+/// Battery status as tracked by the low power monitor, for applets and OSDs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Power {
+    pub level: &'static str,
+    pub ac_plugged: bool,
+    pub percentage: f64,
+    pub time_to_empty: i64,
+    pub time_to_full: i64,
+}
+
+impl Default for Power {
+    fn default() -> Self {
+        Self {
+            level: level_name(BatteryLevel::Normal),
+            ac_plugged: true,
+            percentage: 0.0,
+            time_to_empty: 0,
+            time_to_full: 0,
+        }
+    }
+}
+
+#[zbus::interface(name = "com.system76.CosmicSettingsDaemon.Power")]
+impl Power {
+    /// `Normal`, `Low`, `Critical`, or `Full`, by the configured thresholds.
+    #[zbus(property)]
+    async fn level(&self) -> String {
+        self.level.to_owned()
+    }
+
+    #[zbus(property)]
+    async fn ac_plugged(&self) -> bool {
+        self.ac_plugged
+    }
+
+    #[zbus(property)]
+    async fn percentage(&self) -> f64 {
+        self.percentage
+    }
+
+    /// Estimated seconds until the battery is empty, or 0 if unknown.
+    #[zbus(property)]
+    async fn time_to_empty(&self) -> i64 {
+        self.time_to_empty
+    }
+
+    /// Estimated seconds until the battery is full, or 0 if unknown.
+    #[zbus(property)]
+    async fn time_to_full(&self) -> i64 {
+        self.time_to_full
+    }
+
+    /// Battery samples recorded at or after the Unix time `since`, as
+    /// `(time, percentage, energy rate in W, capacity in percent, design energy in Wh)`.
+    async fn get_battery_history(&self, since: u64) -> Vec<battery_history::Sample> {
+        battery_history::load(since)
+    }
+
+    /// Emitted when the battery level changes, with the new level.
+    #[zbus(signal, name = "LevelChanged")]
+    async fn level_transition(ctxt: &SignalContext<'_>, level: &str) -> zbus::Result<()>;
+}
+
+pub fn level_name(level: BatteryLevel) -> &'static str {
+    match level {
+        BatteryLevel::Full => "Full",
+        BatteryLevel::Low => "Low",
+        BatteryLevel::Critical => "Critical",
+        _ => "Normal",
+    }
+}
+
+/// Replace the published battery status, notifying clients of what changed.
+pub async fn publish(connection: &zbus::Connection, status: Power) {
+    let Ok(interface) = connection
+        .object_server()
+        .interface::<_, Power>(DBUS_PATH)
+        .await
+    else {
+        return;
+    };
+
+    let mut power = interface.get_mut().await;
+    if *power == status {
+        return;
+    }
+    let previous = std::mem::replace(&mut *power, status);
+
+    let ctxt = interface.signal_context();
+    if previous.level != power.level {
+        _ = power.level_changed(ctxt).await;
+        _ = Power::level_transition(ctxt, power.level).await;
+    }
+    if previous.ac_plugged != power.ac_plugged {
+        _ = power.ac_plugged_changed(ctxt).await;
+    }
+    if previous.percentage != power.percentage {
+        _ = power.percentage_changed(ctxt).await;
+    }
+    if previous.time_to_empty != power.time_to_empty {
+        _ = power.time_to_empty_changed(ctxt).await;
+    }
+    if previous.time_to_full != power.time_to_full {
+        _ = power.time_to_full_changed(ctxt).await;
+    }
+}
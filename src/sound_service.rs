@@ -0,0 +1,58 @@
+// Copyright 2025 System76 <info@system76.com>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Event sounds for other COSMIC components, so that they share the theme
+//! and mute settings of the daemon.
+
+use std::collections::HashMap;
+
+use crate::event_sounds::EventSounds;
+use crate::playback::Playback;
+
+pub struct Sound {
+    sounds: EventSounds,
+    last_id: u32,
+    /// Sounds started by clients, by ID.
+    playing: HashMap<u32, Playback>,
+}
+
+impl Sound {
+    pub fn new(sounds: EventSounds) -> Self {
+        Self {
+            sounds,
+            last_id: 0,
+            playing: HashMap::new(),
+        }
+    }
+}
+
+#[zbus::interface(name = "com.system76.CosmicSettingsDaemon.Sound")]
+impl Sound {
+    /// Play the sound for an XDG sound naming spec event ID, such as
+    /// `bell` or `screen-capture`, from the configured sound theme.
+    ///
+    /// Properties are modelled after those of libcanberra's
+    /// `ca_context_play`, such as `media.role` and `event.description`.
+    ///
+    /// Returns an ID for `CancelSound`, or 0 if nothing is played because
+    /// event sounds are disabled, the theme has no sound for the event, or
+    /// `media.filename` is not a file in the sound directories.
+    async fn play_sound(&mut self, event_id: String, properties: HashMap<String, String>) -> u32 {
+        let Some(playback) = self.sounds.play_with_properties(&event_id, &properties) else {
+            return 0;
+        };
+
+        // IDs are never 0, which signals that nothing is played.
+        self.last_id = self.last_id.checked_add(1).unwrap_or(1);
+        self.playing.retain(|_, playback| !playback.is_finished());
+        self.playing.insert(self.last_id, playback);
+        self.last_id
+    }
+
+    /// Stop a sound started with `PlaySound`, if it is still playing.
+    async fn cancel_sound(&mut self, id: u32) {
+        if let Some(playback) = self.playing.remove(&id) {
+            playback.cancel();
+        }
+    }
+}
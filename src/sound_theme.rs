@@ -0,0 +1,416 @@
+// Copyright 2025 System76 <info@system76.com>
+// SPDX-License-Identifier: MPL-2.0
+
+//! Sound lookup following the XDG Sound Theme and Sound Naming specifications.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Theme searched when the sound is missing from the requested theme.
+const FALLBACK_THEME: &str = "freedesktop";
+
+/// Output profile of the sound theme directories to use.
+const OUTPUT_PROFILE: &str = "stereo";
+
+/// Supported sound file extensions, in order of preference.
+const EXTENSIONS: &[&str] = &["oga", "ogg", "wav"];
+
+/// Contents of a theme's `index.theme` relevant to sound lookup.
+#[derive(Debug, Default)]
+struct ThemeIndex {
+    inherits: Vec<String>,
+    directories: Vec<String>,
+}
+
+impl ThemeIndex {
+    fn parse(contents: &str) -> Self {
+        let mut index = Self::default();
+        let mut section = "";
+        let mut output_profiles = Vec::new();
+
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name;
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let list = || {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(str::to_owned)
+                    .collect::<Vec<_>>()
+            };
+
+            match (section, key.trim()) {
+                ("Sound Theme", "Inherits") => index.inherits = list(),
+                ("Sound Theme", "Directories") => index.directories = list(),
+                (directory, "OutputProfile") => {
+                    output_profiles.push((directory.to_owned(), value.trim().to_owned()))
+                }
+                _ => (),
+            }
+        }
+
+        // Directories for other output profiles, such as 5.1, are skipped.
+        index.directories.retain(|directory| {
+            output_profiles
+                .iter()
+                .find(|(name, _)| name == directory)
+                .is_none_or(|(_, profile)| profile == OUTPUT_PROFILE)
+        });
+
+        index
+    }
+}
+
+/// Resolves sound event IDs to files in the installed sound themes.
+pub struct SoundThemeResolver {
+    /// Base directories containing sound themes, in order of precedence.
+    base_dirs: Vec<PathBuf>,
+    /// Locale subdirectories to try, from most to least specific.
+    locales: Vec<String>,
+}
+
+impl SoundThemeResolver {
+    /// Search the XDG data directories, with localized sounds for the
+    /// session locale.
+    pub fn new() -> Self {
+        let data_home = std::env::var_os("XDG_DATA_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| dirs::home_dir().map(|home| home.join(".local/share")));
+
+        let data_dirs = std::env::var("XDG_DATA_DIRS")
+            .ok()
+            .filter(|dirs| !dirs.is_empty())
+            .unwrap_or_else(|| "/usr/local/share:/usr/share".to_owned());
+
+        let base_dirs = data_home
+            .into_iter()
+            .chain(
+                data_dirs
+                    .split(':')
+                    .filter(|dir| !dir.is_empty())
+                    .map(PathBuf::from),
+            )
+            .map(|dir| dir.join("sounds"))
+            .collect();
+
+        let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+
+        Self::with_dirs(base_dirs, &locale)
+    }
+
+    /// Search the given sound base directories, with localized sounds for `locale`.
+    pub fn with_dirs(base_dirs: Vec<PathBuf>, locale: &str) -> Self {
+        Self {
+            base_dirs,
+            locales: locale_variants(locale),
+        }
+    }
+
+    /// The canonical path of `path` if it is a file within the sound base
+    /// directories, so that clients cannot have arbitrary files played.
+    pub fn sound_file(&self, path: &Path) -> Option<PathBuf> {
+        let path = path.canonicalize().ok()?;
+        let in_base_dir = self.base_dirs.iter().any(|base_dir| {
+            base_dir
+                .canonicalize()
+                .is_ok_and(|base_dir| path.starts_with(base_dir))
+        });
+        (in_base_dir && path.is_file()).then_some(path)
+    }
+
+    /// Find the sound file for an event ID, preferring the given theme.
+    ///
+    /// Sounds missing from the theme are looked up in the themes it inherits
+    /// and then in the `freedesktop` theme. If no theme has the sound, the
+    /// last dash-separated component of the event ID is stripped and the
+    /// lookup repeated, so that `battery-caution` falls back to `battery`.
+    pub fn lookup(&self, theme: &str, event_id: &str) -> Option<PathBuf> {
+        if !is_file_name(event_id) {
+            return None;
+        }
+
+        let mut name = event_id;
+        loop {
+            let mut visited = HashSet::new();
+            let found = self
+                .lookup_in_theme(theme, name, &mut visited)
+                .or_else(|| self.lookup_in_theme(FALLBACK_THEME, name, &mut visited))
+                .or_else(|| self.lookup_unthemed(name));
+            if found.is_some() {
+                return found;
+            }
+
+            name = &name[..name.rfind('-')?];
+        }
+    }
+
+    /// Look up a sound in a theme and the themes it inherits.
+    fn lookup_in_theme(
+        &self,
+        theme: &str,
+        name: &str,
+        visited: &mut HashSet<String>,
+    ) -> Option<PathBuf> {
+        // Themes may inherit each other, so guard against cycles.
+        if !is_file_name(theme) || !visited.insert(theme.to_owned()) {
+            return None;
+        }
+
+        let index = self.theme_index(theme)?;
+        if let Some(path) = self.lookup_sound(theme, &index.directories, name) {
+            return Some(path);
+        }
+
+        index
+            .inherits
+            .iter()
+            .find_map(|parent| self.lookup_in_theme(parent, name, visited))
+    }
+
+    /// The index of a theme, taken from the first base directory providing one.
+    fn theme_index(&self, theme: &str) -> Option<ThemeIndex> {
+        self.base_dirs.iter().find_map(|base| {
+            fs::read_to_string(base.join(theme).join("index.theme"))
+                .ok()
+                .map(|contents| ThemeIndex::parse(&contents))
+        })
+    }
+
+    /// Look up a sound in the directories of a single theme, preferring
+    /// localized sounds. Files for a theme may be spread over several base
+    /// directories.
+    fn lookup_sound(&self, theme: &str, directories: &[String], name: &str) -> Option<PathBuf> {
+        // Be lenient with themes keeping their sounds at the top level.
+        let top_level = [String::new()];
+        let directories = if directories.is_empty() {
+            &top_level[..]
+        } else {
+            directories
+        };
+
+        let locales = self.locales.iter().map(Some).chain([None]);
+        for locale in locales {
+            for directory in directories {
+                for base in &self.base_dirs {
+                    let mut dir = base.join(theme).join(directory);
+                    if let Some(locale) = locale {
+                        dir.push(locale);
+                    }
+
+                    if let Some(path) = find_file(&dir, name) {
+                        return Some(path);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Look up a sound placed directly in a base directory, outside of any theme.
+    fn lookup_unthemed(&self, name: &str) -> Option<PathBuf> {
+        self.base_dirs.iter().find_map(|base| find_file(base, name))
+    }
+}
+
+/// Whether a theme or sound name stays within the directory it is joined
+/// onto, rather than being a path such as `../../tmp/x`.
+fn is_file_name(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with('.') && !name.contains('/')
+}
+
+/// Find a sound file named `name` in `dir`, with the most preferred extension.
+fn find_file(dir: &Path, name: &str) -> Option<PathBuf> {
+    EXTENSIONS.iter().find_map(|extension| {
+        let path = dir.join(format!("{name}.{extension}"));
+        path.is_file().then_some(path)
+    })
+}
+
+/// Locale subdirectory names to try for a POSIX locale such as
+/// `de_DE.UTF-8@euro`, from most to least specific.
+fn locale_variants(locale: &str) -> Vec<String> {
+    let (locale, modifier) = match locale.split_once('@') {
+        Some((locale, modifier)) => (locale, Some(modifier)),
+        None => (locale, None),
+    };
+    let locale = locale.split('.').next().unwrap_or_default();
+    if locale.is_empty() || locale == "C" || locale == "POSIX" {
+        return Vec::new();
+    }
+
+    let language = locale.split('_').next().unwrap_or(locale);
+    let mut variants = Vec::new();
+    for candidate in [locale, language] {
+        if let Some(modifier) = modifier {
+            variants.push(format!("{candidate}@{modifier}"));
+        }
+        variants.push(candidate.to_owned());
+    }
+    variants.dedup();
+    variants
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    /// A fake tree of sound theme base directories.
+    struct FakeSounds(TempDir);
+
+    impl FakeSounds {
+        fn new(name: &str) -> Self {
+            Self(TempDir::new(&format!("sounds-{name}")))
+        }
+
+        fn base(&self, base: &str) -> PathBuf {
+            self.0.path().join(base)
+        }
+
+        fn theme(&self, base: &str, theme: &str, index: &str) {
+            let dir = self.base(base).join(theme);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("index.theme"), index).unwrap();
+        }
+
+        fn sound(&self, base: &str, path: &str) -> PathBuf {
+            let path = self.base(base).join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, b"").unwrap();
+            path
+        }
+
+        fn resolver(&self, locale: &str) -> SoundThemeResolver {
+            SoundThemeResolver::with_dirs(vec![self.base("user"), self.base("system")], locale)
+        }
+    }
+
+    const FREEDESKTOP_INDEX: &str = "[Sound Theme]\nName=Default\nDirectories=stereo\n\n\
+        [stereo]\nOutputProfile=stereo\n";
+
+    #[test]
+    fn inherits_and_prefers_extensions() {
+        let sounds = FakeSounds::new("inherits");
+        sounds.theme("system", "freedesktop", FREEDESKTOP_INDEX);
+        sounds.theme(
+            "system",
+            "Pop",
+            "[Sound Theme]\nName=Pop\nInherits=base\nDirectories=stereo,5.1\n\n\
+             [stereo]\nOutputProfile=stereo\n\n[5.1]\nOutputProfile=5.1\n",
+        );
+        sounds.theme(
+            "system",
+            "base",
+            "[Sound Theme]\nName=Base\nInherits=Pop\nDirectories=stereo\n",
+        );
+
+        sounds.sound("system", "Pop/5.1/bell.oga");
+        sounds.sound("system", "base/stereo/bell.wav");
+        let bell = sounds.sound("system", "base/stereo/bell.oga");
+        sounds.sound("system", "freedesktop/stereo/bell.oga");
+        let plug = sounds.sound("system", "freedesktop/stereo/power-plug.ogg");
+
+        let resolver = sounds.resolver("");
+        assert_eq!(resolver.lookup("Pop", "bell"), Some(bell));
+        assert_eq!(resolver.lookup("Pop", "power-plug"), Some(plug));
+        assert_eq!(resolver.lookup("Pop", "missing"), None);
+    }
+
+    #[test]
+    fn strips_event_id_suffixes() {
+        let sounds = FakeSounds::new("suffixes");
+        sounds.theme("system", "freedesktop", FREEDESKTOP_INDEX);
+        sounds.theme("system", "Pop", "[Sound Theme]\nDirectories=stereo\n");
+
+        let battery = sounds.sound("system", "Pop/stereo/battery.oga");
+        let caution = sounds.sound("system", "freedesktop/stereo/battery-caution.oga");
+
+        let resolver = sounds.resolver("");
+        assert_eq!(resolver.lookup("Pop", "battery-caution"), Some(caution));
+        assert_eq!(resolver.lookup("Pop", "battery-low"), Some(battery.clone()));
+        assert_eq!(resolver.lookup("Pop", "battery-very-low"), Some(battery));
+    }
+
+    #[test]
+    fn user_and_localized_sounds() {
+        let sounds = FakeSounds::new("locales");
+        sounds.theme("system", "freedesktop", FREEDESKTOP_INDEX);
+
+        let system = sounds.sound("system", "freedesktop/stereo/bell.oga");
+        let user = sounds.sound("user", "freedesktop/stereo/bell.oga");
+        let german = sounds.sound("system", "freedesktop/stereo/de/bell.oga");
+        let unthemed = sounds.sound("system", "complete.wav");
+
+        assert_eq!(
+            sounds.resolver("C").lookup("freedesktop", "bell"),
+            Some(user.clone())
+        );
+        assert_eq!(
+            sounds.resolver("de_DE.UTF-8").lookup("freedesktop", "bell"),
+            Some(german)
+        );
+        assert_eq!(
+            sounds.resolver("fr_FR").lookup("freedesktop", "bell"),
+            Some(user.clone())
+        );
+        assert_eq!(
+            sounds.resolver("").lookup("Pop", "complete"),
+            Some(unthemed)
+        );
+
+        fs::remove_file(user).unwrap();
+        assert_eq!(
+            sounds.resolver("").lookup("freedesktop", "bell"),
+            Some(system)
+        );
+    }
+
+    #[test]
+    fn locale_variants_from_posix_locale() {
+        assert_eq!(
+            locale_variants("de_DE.UTF-8@euro"),
+            ["de_DE@euro", "de_DE", "de@euro", "de"]
+        );
+        assert_eq!(locale_variants("pt_BR"), ["pt_BR", "pt"]);
+        assert_eq!(locale_variants("en"), ["en"]);
+        assert!(locale_variants("C.UTF-8").is_empty());
+    }
+
+    #[test]
+    fn only_accepts_files_in_base_dirs() {
+        let sounds = FakeSounds::new("sound-file");
+        let bell = sounds.sound("user", "Pop/stereo/bell.oga");
+        let outside = sounds.sound("other", "bell.oga");
+
+        let resolver = sounds.resolver("");
+        assert_eq!(resolver.sound_file(&bell), Some(bell.clone()));
+        assert_eq!(
+            resolver.sound_file(&sounds.base("user").join("../other/bell.oga")),
+            None
+        );
+        assert_eq!(resolver.sound_file(&outside), None);
+        assert_eq!(resolver.sound_file(&sounds.base("user").join("Pop")), None);
+        assert_eq!(
+            resolver.sound_file(&sounds.base("user").join("missing.oga")),
+            None
+        );
+    }
+}
@@ -0,0 +1,50 @@
+// Copyright 2025 System76 <info@system76.com>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Helpers shared by unit tests.
+
+use std::path::{Path, PathBuf};
+
+use zbus::{object_server::Interface, Connection};
+
+/// Connect a client to a peer-to-peer server serving a mock interface at `path`.
+///
+/// Further mock objects can be added through the server's object server.
+/// Returns the server and client connections.
+pub async fn p2p(path: &str, mock: impl Interface) -> (Connection, Connection) {
+    let (server, client) = tokio::net::UnixStream::pair().unwrap();
+    let server = zbus::connection::Builder::unix_stream(server)
+        .server(zbus::Guid::generate())
+        .unwrap()
+        .p2p()
+        .serve_at(path, mock)
+        .unwrap()
+        .build();
+    let client = zbus::connection::Builder::unix_stream(client).p2p().build();
+    futures_util::try_join!(server, client).unwrap()
+}
+
+/// A temporary directory unique to the test and process, removed when dropped.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "cosmic-settings-daemon-{name}-{}",
+            std::process::id()
+        ));
+        _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,68 @@
+use zbus::zvariant::{ObjectPath, OwnedObjectPath};
+
+/// `UpDeviceKind` of a battery.
+pub const KIND_BATTERY: u32 = 2;
+
+#[zbus::proxy(
+    default_service = "org.freedesktop.UPower",
+    interface = "org.freedesktop.UPower",
+    default_path = "/org/freedesktop/UPower"
+)]
+trait UPower {
+    fn enumerate_devices(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    /// The composite device combining the batteries powering the system.
+    #[zbus(object = "Device")]
+    fn get_display_device(&self);
+
+    #[zbus(signal)]
+    fn device_added(&self, device: ObjectPath<'_>) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn device_removed(&self, device: ObjectPath<'_>) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn on_battery(&self) -> zbus::Result<bool>;
+}
+
+#[zbus::proxy(
+    default_service = "org.freedesktop.UPower",
+    interface = "org.freedesktop.UPower.Device"
+)]
+trait Device {
+    /// The `UpDeviceKind` of the device.
+    #[zbus(property, name = "Type")]
+    fn kind(&self) -> zbus::Result<u32>;
+
+    #[zbus(property)]
+    fn model(&self) -> zbus::Result<String>;
+
+    /// Health of the battery, as a percentage of its design capacity.
+    #[zbus(property)]
+    fn capacity(&self) -> zbus::Result<f64>;
+
+    /// Rate of discharge or charge, in W.
+    #[zbus(property)]
+    fn energy_rate(&self) -> zbus::Result<f64>;
+
+    /// Energy of the battery when full as designed, in Wh.
+    #[zbus(property)]
+    fn energy_full_design(&self) -> zbus::Result<f64>;
+
+    #[zbus(property)]
+    fn is_present(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn percentage(&self) -> zbus::Result<f64>;
+
+    #[zbus(property)]
+    fn power_supply(&self) -> zbus::Result<bool>;
+
+    /// Estimated time until empty, in seconds, or 0 if unknown.
+    #[zbus(property)]
+    fn time_to_empty(&self) -> zbus::Result<i64>;
+
+    /// Estimated time until fully charged, in seconds, or 0 if unknown.
+    #[zbus(property)]
+    fn time_to_full(&self) -> zbus::Result<i64>;
+}
@@ -0,0 +1,237 @@
+// Copyright 2025 System76 <info@system76.com>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Volume and mute of the default audio devices, through WirePlumber's `wpctl`
+//! or, where it is not installed, `pactl`.
+
+use std::io;
+
+use tokio::process::Command;
+
+use crate::playback::in_path;
+
+/// A default audio device.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Device {
+    /// The default output, such as speakers or headphones.
+    Sink,
+    /// The default input, such as a microphone.
+    Source,
+}
+
+impl Device {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Sink => "sink",
+            Self::Source => "source",
+        }
+    }
+
+    fn target(self, tool: Tool) -> &'static str {
+        match (tool, self) {
+            (Tool::Wpctl, Self::Sink) => "@DEFAULT_AUDIO_SINK@",
+            (Tool::Wpctl, Self::Source) => "@DEFAULT_AUDIO_SOURCE@",
+            (Tool::Pactl, Self::Sink) => "@DEFAULT_SINK@",
+            (Tool::Pactl, Self::Source) => "@DEFAULT_SOURCE@",
+        }
+    }
+}
+
+/// The command controlling the audio devices.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Tool {
+    /// WirePlumber's `wpctl`.
+    Wpctl,
+    /// PulseAudio's `pactl`, also provided by `pipewire-pulse` setups.
+    Pactl,
+}
+
+impl Tool {
+    fn detect() -> Self {
+        if in_path("wpctl") {
+            Self::Wpctl
+        } else {
+            Self::Pactl
+        }
+    }
+
+    fn program(self) -> &'static str {
+        match self {
+            Self::Wpctl => "wpctl",
+            Self::Pactl => "pactl",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Volume {
+    /// Volume in percent, which may exceed 100 when boosted.
+    pub percent: u32,
+    pub muted: bool,
+}
+
+/// Parse the output of `wpctl get-volume`, such as `Volume: 0.40 [MUTED]`.
+fn parse_volume(output: &str) -> Option<Volume> {
+    let mut words = output.trim().strip_prefix("Volume:")?.split_whitespace();
+    let volume = words.next()?.parse::<f64>().ok()?;
+    Some(Volume {
+        percent: (volume * 100.0).round().max(0.0) as u32,
+        muted: words.any(|word| word == "[MUTED]"),
+    })
+}
+
+/// Parse the output of `pactl get-sink-volume` and `pactl get-sink-mute`,
+/// taking the volume of the first channel.
+fn parse_pactl_volume(volume: &str, mute: &str) -> Option<Volume> {
+    let percent = volume
+        .trim()
+        .strip_prefix("Volume:")?
+        .split_whitespace()
+        .find_map(|word| word.strip_suffix('%'))?
+        .parse()
+        .ok()?;
+    let muted = match mute.trim().strip_prefix("Mute:")?.trim() {
+        "yes" => true,
+        "no" => false,
+        _ => return None,
+    };
+    Some(Volume { percent, muted })
+}
+
+async fn run(tool: Tool, args: &[&str]) -> io::Result<String> {
+    let output = Command::new(tool.program())
+        .args(args)
+        // The output of `pactl` is translated.
+        .env("LC_ALL", "C")
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "{} {} failed: {}",
+            tool.program(),
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+async fn get(tool: Tool, device: Device) -> io::Result<Volume> {
+    let target = device.target(tool);
+    let (volume, output) = match tool {
+        Tool::Wpctl => {
+            let output = run(tool, &["get-volume", target]).await?;
+            (parse_volume(&output), output)
+        }
+        Tool::Pactl => {
+            let volume_command = format!("get-{}-volume", device.as_str());
+            let mute_command = format!("get-{}-mute", device.as_str());
+            let volume = run(tool, &[&volume_command, target]).await?;
+            let mute = run(tool, &[&mute_command, target]).await?;
+            (parse_pactl_volume(&volume, &mute), volume + &mute)
+        }
+    };
+    volume.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unexpected {} output: {output}", tool.program()),
+        )
+    })
+}
+
+/// Mute the device if `mute` is `"1"`, unmute it if `"0"`, or toggle it.
+async fn set_mute(tool: Tool, device: Device, mute: &str) -> io::Result<()> {
+    let command = match tool {
+        Tool::Wpctl => "set-mute".to_owned(),
+        Tool::Pactl => format!("set-{}-mute", device.as_str()),
+    };
+    run(tool, &[&command, device.target(tool), mute]).await?;
+    Ok(())
+}
+
+async fn set_volume(tool: Tool, device: Device, percent: u32) -> io::Result<()> {
+    let command = match tool {
+        Tool::Wpctl => "set-volume".to_owned(),
+        Tool::Pactl => format!("set-{}-volume", device.as_str()),
+    };
+    let volume = format!("{percent}%");
+    run(tool, &[&command, device.target(tool), &volume]).await?;
+    Ok(())
+}
+
+/// Raise the volume by `step` percent, up to `max` percent, unmuting the device.
+/// A volume already above `max` is left alone.
+pub async fn raise(device: Device, step: u32, max: u32) -> io::Result<Volume> {
+    let tool = Tool::detect();
+    set_mute(tool, device, "0").await?;
+    let volume = get(tool, device).await?;
+    if volume.percent < max {
+        set_volume(tool, device, volume.percent.saturating_add(step).min(max)).await?;
+    }
+    get(tool, device).await
+}
+
+/// Lower the volume by `step` percent, unmuting the device.
+pub async fn lower(device: Device, step: u32) -> io::Result<Volume> {
+    let tool = Tool::detect();
+    set_mute(tool, device, "0").await?;
+    let volume = get(tool, device).await?;
+    set_volume(tool, device, volume.percent.saturating_sub(step)).await?;
+    get(tool, device).await
+}
+
+pub async fn toggle_mute(device: Device) -> io::Result<Volume> {
+    let tool = Tool::detect();
+    set_mute(tool, device, "toggle").await?;
+    get(tool, device).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_wpctl_volume() {
+        assert_eq!(
+            parse_volume("Volume: 0.40\n"),
+            Some(Volume {
+                percent: 40,
+                muted: false
+            })
+        );
+        assert_eq!(
+            parse_volume("Volume: 1.25 [MUTED]\n"),
+            Some(Volume {
+                percent: 125,
+                muted: true
+            })
+        );
+        assert_eq!(parse_volume("Translate ID error: id is not valid"), None);
+    }
+
+    #[test]
+    fn parses_pactl_volume() {
+        assert_eq!(
+            parse_pactl_volume(
+                "Volume: front-left: 26214 /  40% / -23.88 dB,   \
+                 front-right: 26214 /  40% / -23.88 dB\n        balance 0.00\n",
+                "Mute: no\n"
+            ),
+            Some(Volume {
+                percent: 40,
+                muted: false
+            })
+        );
+        assert_eq!(
+            parse_pactl_volume("Volume: mono: 81920 / 125% / 5.81 dB\n", "Mute: yes\n"),
+            Some(Volume {
+                percent: 125,
+                muted: true
+            })
+        );
+        assert_eq!(
+            parse_pactl_volume("Connection failure: Connection refused", "Mute: no"),
+            None
+        );
+    }
+}
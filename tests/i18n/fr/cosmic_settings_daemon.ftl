@@ -0,0 +1,3 @@
+# Partial translation used to test the fallback to English.
+
+battery-low = Batterie faible